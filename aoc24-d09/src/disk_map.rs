@@ -0,0 +1,259 @@
+/// The disk, one entry per block: the ID of the file it belongs to, or None when it's free.
+pub struct DiskMap {
+    blocks: Vec<Option<usize>>,
+}
+
+pub struct Fragmentation {
+    pub files: usize,
+    // files that are spread over more than one run of blocks
+    pub fragmented_files: usize,
+    // runs of free blocks before the last file block, these are the holes compaction tries to fill
+    pub gaps: usize,
+    pub gap_blocks: usize,
+    pub largest_gap: usize,
+}
+
+pub struct CompactionReport {
+    pub strategy: &'static str,
+    pub checksum: usize,
+    pub moves: usize,
+    pub fragmentation: Fragmentation,
+    pub layout: String,
+}
+
+pub trait CompactionStrategy {
+    fn name(&self) -> &'static str;
+    /// Rearranges the blocks on the disk and returns the number of moves it took.
+    fn compact(&self, disk: &mut DiskMap) -> usize;
+}
+
+impl DiskMap {
+    pub fn parse(input: &str) -> DiskMap {
+        let mut blocks = Vec::with_capacity(input.len() * 9);
+        for (index, number) in input.trim_end().char_indices() {
+            let size = number.to_digit(10).unwrap() as usize;
+            let block = if index % 2 == 0 {
+                Some(index / 2)
+            } else {
+                None
+            };
+            blocks.extend(std::iter::repeat_n(block, size));
+        }
+        DiskMap { blocks }
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| block.map_or(0, |id| id * i))
+            .sum()
+    }
+
+    /// Renders the disk like the puzzle does, a digit for each file block and a '.' for free blocks.
+    /// There are only ten digits, so file IDs of 10 and up continue with letters and wrap around after 'z'.
+    pub fn render(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| match block {
+                Some(id) => char::from_digit((id % 36) as u32, 36).unwrap(),
+                None => '.',
+            })
+            .collect()
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        let mut runs_per_file: Vec<usize> = Vec::new();
+        let mut gaps = Vec::new();
+        let end = self
+            .blocks
+            .iter()
+            .rposition(|b| b.is_some())
+            .map_or(0, |i| i + 1);
+        for (_, length, block) in self.runs(0..end) {
+            match block {
+                Some(id) => {
+                    if runs_per_file.len() <= id {
+                        runs_per_file.resize(id + 1, 0);
+                    }
+                    runs_per_file[id] += 1;
+                }
+                None => gaps.push(length),
+            }
+        }
+
+        Fragmentation {
+            files: runs_per_file.iter().filter(|&&runs| runs > 0).count(),
+            fragmented_files: runs_per_file.iter().filter(|&&runs| runs > 1).count(),
+            gaps: gaps.len(),
+            gap_blocks: gaps.iter().sum(),
+            largest_gap: gaps.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// Groups the blocks in the range in runs of the same content: (start, length, content).
+    fn runs(&self, range: std::ops::Range<usize>) -> Vec<(usize, usize, Option<usize>)> {
+        let mut runs: Vec<(usize, usize, Option<usize>)> = Vec::new();
+        for i in range {
+            match runs.last_mut() {
+                Some((_, length, block)) if *block == self.blocks[i] => *length += 1,
+                _ => runs.push((i, 1, self.blocks[i])),
+            }
+        }
+        runs
+    }
+
+    fn move_blocks(&mut self, from: usize, to: usize, length: usize) {
+        for i in 0..length {
+            self.blocks[to + i] = self.blocks[from + i].take();
+        }
+    }
+}
+
+pub fn compact(input: &str, strategy: &dyn CompactionStrategy) -> CompactionReport {
+    let mut disk = DiskMap::parse(input);
+    let moves = strategy.compact(&mut disk);
+    CompactionReport {
+        strategy: strategy.name(),
+        checksum: disk.checksum(),
+        moves,
+        fragmentation: disk.fragmentation(),
+        layout: disk.render(),
+    }
+}
+
+/// Part 1: move single blocks from the end of the disk to the leftmost free block.
+/// Every block that is moved counts as a move.
+pub struct BlockLevel;
+
+impl CompactionStrategy for BlockLevel {
+    fn name(&self) -> &'static str {
+        "block-level"
+    }
+
+    fn compact(&self, disk: &mut DiskMap) -> usize {
+        let mut moves = 0;
+        let mut i_insert = 0;
+        let mut i_end = disk.blocks.len();
+        while i_insert < i_end {
+            if disk.blocks[i_insert].is_some() {
+                i_insert += 1;
+            } else if disk.blocks[i_end - 1].is_none() {
+                i_end -= 1;
+            } else {
+                disk.move_blocks(i_end - 1, i_insert, 1);
+                moves += 1;
+            }
+        }
+        moves
+    }
+}
+
+/// Part 2: move whole files, highest ID first, to the leftmost free span they fit in.
+pub struct FirstFit;
+
+/// Like first-fit, but picks the smallest free span the file fits in (leftmost when there's a tie).
+pub struct BestFit;
+
+impl CompactionStrategy for FirstFit {
+    fn name(&self) -> &'static str {
+        "first-fit"
+    }
+
+    fn compact(&self, disk: &mut DiskMap) -> usize {
+        move_whole_files(disk, |spans| Some(spans.first()?.0))
+    }
+}
+
+impl CompactionStrategy for BestFit {
+    fn name(&self) -> &'static str {
+        "best-fit"
+    }
+
+    fn compact(&self, disk: &mut DiskMap) -> usize {
+        move_whole_files(disk, |spans| {
+            let (i, _) = spans.iter().min_by_key(|(_, (_, length))| *length)?;
+            Some(*i)
+        })
+    }
+}
+
+/// Shared by the whole-file strategies: each file is tried once, from the highest ID down,
+/// and can only move to the left. `pick` gets the spans that fit as (index, (start, length)) and returns an index.
+fn move_whole_files(
+    disk: &mut DiskMap,
+    pick: impl Fn(&[(usize, (usize, usize))]) -> Option<usize>,
+) -> usize {
+    let runs = disk.runs(0..disk.blocks.len());
+    let mut free_spans: Vec<(usize, usize)> = runs
+        .iter()
+        .filter(|(_, _, block)| block.is_none())
+        .map(|&(start, length, _)| (start, length))
+        .collect();
+    let mut files: Vec<(usize, usize, usize)> = runs
+        .iter()
+        .filter_map(|&(start, length, block)| Some((block?, start, length)))
+        .collect();
+    files.sort_by_key(|&(id, _, _)| std::cmp::Reverse(id));
+
+    let mut moves = 0;
+    for (_, file_start, file_length) in files {
+        let fitting: Vec<_> = free_spans
+            .iter()
+            .copied()
+            .enumerate()
+            .take_while(|(_, (start, _))| *start < file_start)
+            .filter(|(_, (_, length))| *length >= file_length)
+            .collect();
+        let Some(i) = pick(&fitting) else {
+            continue;
+        };
+
+        let (span_start, span_length) = free_spans[i];
+        disk.move_blocks(file_start, span_start, file_length);
+        free_spans[i] = (span_start + file_length, span_length - file_length);
+        moves += 1;
+    }
+    moves
+}
+
+/// Packs all files together at the start of the disk, in order of their ID, without any gaps.
+/// Every file that ends up at a different position counts as a move.
+pub struct Defragment;
+
+impl CompactionStrategy for Defragment {
+    fn name(&self) -> &'static str {
+        "defragment"
+    }
+
+    fn compact(&self, disk: &mut DiskMap) -> usize {
+        let mut sizes: Vec<usize> = Vec::new();
+        for block in disk.blocks.iter() {
+            if let Some(id) = *block {
+                if sizes.len() <= id {
+                    sizes.resize(id + 1, 0);
+                }
+                sizes[id] += 1;
+            }
+        }
+
+        let mut moves = 0;
+        let mut position = 0;
+        for (id, &size) in sizes.iter().enumerate() {
+            if size == 0 {
+                continue;
+            }
+            // blocks from this position on haven't been overwritten yet, so we can still see if the file was in place
+            if disk.blocks[position..position + size]
+                .iter()
+                .any(|b| *b != Some(id))
+            {
+                moves += 1;
+            }
+            disk.blocks[position..position + size].fill(Some(id));
+            position += size;
+        }
+        disk.blocks[position..].fill(None);
+        moves
+    }
+}
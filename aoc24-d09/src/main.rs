@@ -1,4 +1,13 @@
-﻿use std::fs::read_to_string;
+﻿use aoc24_tools::{get_argument, has_flag};
+use disk_map::{compact, BestFit, BlockLevel, CompactionStrategy, Defragment, FirstFit};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::Infallible;
+use std::fs::{read_to_string, File};
+use stream::move_file_blocks_to_front_from_reader;
+
+mod disk_map;
+mod stream;
 
 const DAY: u8 = 9;
 
@@ -21,6 +30,40 @@ fn main() {
     let lines = read_to_string("src/main.rs").unwrap().lines().count();
     let os_arch = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
     println!("\n| Day {DAY} | \u{1F980} Rust {version} | \u{23F1}\u{FE0F} {time:?} ({ns} ns) | \u{1F4DC} {lines} lines | \u{2699}\u{FE0F} {os_arch} |");
+
+    // optional: cargo run -r -- --stream huge_input.txt
+    if let Some(path) = get_argument("stream") {
+        let checksum = move_file_blocks_to_front_from_reader(File::open(path).unwrap()).unwrap();
+        println!("Checksum with fragmented files, streamed (Part 1): {checksum}");
+    }
+
+    // optional: cargo run -r -- --strategies [--layout]
+    if has_flag("strategies") {
+        print_compaction_strategies(&data, has_flag("layout"));
+    }
+}
+
+fn print_compaction_strategies(input: &str, show_layout: bool) {
+    let strategies: [&dyn CompactionStrategy; 4] = [&BlockLevel, &FirstFit, &BestFit, &Defragment];
+    for strategy in strategies {
+        let report = compact(input, strategy);
+        let fragmentation = report.fragmentation;
+        println!(
+            "\n{}: checksum {} after {} moves",
+            report.strategy, report.checksum, report.moves
+        );
+        println!(
+            "  {} files, {} fragmented, {} gaps with {} free blocks (largest gap: {})",
+            fragmentation.files,
+            fragmentation.fragmented_files,
+            fragmentation.gaps,
+            fragmentation.gap_blocks,
+            fragmentation.largest_gap
+        );
+        if show_layout {
+            println!("  {}", report.layout);
+        }
+    }
 }
 
 fn solve(input: &str) -> (usize, usize) {
@@ -29,121 +72,210 @@ fn solve(input: &str) -> (usize, usize) {
     (part1, part2)
 }
 
-enum DiskBlock {
-    Free,
-    File(usize), //usize = file ID
+fn move_file_blocks_to_front(input: &str) -> usize {
+    // Work directly on the digits of the disk map, from the front and from the back
+    let digits = input.trim_end().as_bytes();
+    let mut front = digits.iter();
+    let mut back = digits.iter().rev();
+    let result: Result<usize, Infallible> = compact_blocks(
+        digits.len(),
+        || Ok(front.next().unwrap() - b'0'),
+        || Ok(back.next().unwrap() - b'0'),
+    );
+    result.unwrap()
 }
 
-fn move_file_blocks_to_front(input: &str) -> usize {
-    // Parse as one long set of File-blocks and Free-blocks spaces
-    let mut disk = Vec::with_capacity(input.len() * 9);
-    for (index, number) in input.char_indices() {
-        let number = number.to_digit(10).unwrap();
-        if index % 2 == 0 {
-            for _ in 0..number {
-                disk.push(DiskBlock::File(index / 2))
-            }
-        } else {
-            for _ in 0..number {
-                disk.push(DiskBlock::Free)
-            }
-        }
+/// Calculates the checksum after moving blocks from the end to the leftmost free block, without building the disk.
+/// `next_front` gives the digits of the disk map from the start, `next_back` from the end. Both are called at most
+/// once for each digit, so the digits can come from anywhere, like a file that is too big to fit in memory.
+fn compact_blocks<E>(
+    number_of_digits: usize,
+    mut next_front: impl FnMut() -> Result<u8, E>,
+    mut next_back: impl FnMut() -> Result<u8, E>,
+) -> Result<usize, E> {
+    if number_of_digits == 0 {
+        return Ok(0);
     }
 
-    // Loop over it, we are working from the outside to the center:
-    let mut i_insert = 0;
-    let mut i_end = disk.len() - 1;
-
-    while i_insert < i_end {
-        // skip when there is nothing to do:
-        if let DiskBlock::File(_) = disk[i_insert] {
-            i_insert += 1;
-            continue;
-        }
-        if let DiskBlock::Free = disk[i_end] {
-            i_end -= 1;
-            continue;
-        }
-
-        // let's move the file at the end to the free space at the front
-        let removed = disk.remove(i_end);
-        disk[i_insert] = removed;
-        i_insert += 1;
-        i_end -= 1;
+    // the digit at an even index is a file, with the index / 2 as ID, the others are free space.
+    // the back always points to a file: skip the free space at the end.
+    let mut i_back = number_of_digits - 1;
+    let mut remaining_back = next_back()? as usize;
+    if i_back % 2 == 1 {
+        i_back -= 1;
+        remaining_back = next_back()? as usize;
     }
 
-    calculate_checksum_on_blocks(disk.as_slice())
-}
-
-fn calculate_checksum_on_blocks(disk: &[DiskBlock]) -> usize {
     let mut checksum = 0;
-    for i in 0..disk.len() {
-        match &disk[i] {
-            DiskBlock::File(id) => checksum += id * i,
-            _ => {}
+    let mut position = 0;
+    let mut i_front = 0;
+    while i_front < i_back {
+        let size = next_front()? as usize;
+        if i_front % 2 == 0 {
+            // a file that stays where it is
+            checksum += add_blocks_to_checksum(i_front / 2, position, size);
+            position += size;
+        } else {
+            // free space: fill it with the blocks of the files at the end
+            let mut free = size;
+            while free > 0 && i_front < i_back {
+                let moved = free.min(remaining_back);
+                checksum += add_blocks_to_checksum(i_back / 2, position, moved);
+                position += moved;
+                free -= moved;
+                remaining_back -= moved;
+                if remaining_back == 0 {
+                    // skip the free space in between, and continue with the next file from the back
+                    next_back()?;
+                    i_back -= 2;
+                    remaining_back = next_back()? as usize;
+                }
+            }
         }
+        i_front += 1;
     }
-    checksum
+
+    // both ends met at a file, the blocks that weren't moved yet stay where they are
+    if i_front == i_back {
+        checksum += add_blocks_to_checksum(i_back / 2, position, remaining_back);
+    }
+    Ok(checksum)
 }
 
-enum DiskFragment {
-    Free(u8),        // u8 = size
-    File(usize, u8), //usize = file ID, u8 = size
+fn add_blocks_to_checksum(id: usize, position: usize, size: usize) -> usize {
+    // id * (position + (position + 1) + ... + (position + size - 1))
+    id * (position * size + size * size.saturating_sub(1) / 2)
 }
 
 fn move_complete_files_to_front(input: &str) -> usize {
-    // Parse it as a set of fragments, each contains its own size.
-    let mut disk = Vec::with_capacity(input.len() * 2);
-    for (index, number) in input.char_indices() {
-        let size = number.to_digit(10).unwrap() as u8;
+    // Parse the files as (offset, size), the file ID is the index.
+    // Free spans are kept in a min-heap per size, so the leftmost span of each size is on top.
+    let mut files = Vec::with_capacity(input.len() / 2 + 1);
+    let mut free_spans: [BinaryHeap<Reverse<usize>>; 10] = Default::default();
+    let mut offset = 0;
+    for (index, number) in input.trim_end().char_indices() {
+        let size = number.to_digit(10).unwrap() as usize;
         if index % 2 == 0 {
-            let id = index / 2;
-            disk.push(DiskFragment::File(id, size))
-        } else {
-            disk.push(DiskFragment::Free(size))
+            files.push((offset, size));
+        } else if size > 0 {
+            free_spans[size].push(Reverse(offset));
         }
+        offset += size;
     }
 
     // Loop over the files from end to beginning.
-    for i in (0..disk.len()).rev() {
-        if let DiskFragment::File(_, file_size) = disk[i] {
-            // time to find a spot to move the file to, loop from start to find first space that fits
-            'replace: for j in 0..i {
-                if let DiskFragment::Free(free_size) = disk[j] {
-                    if free_size >= file_size {
-                        // overwrite free space & put a free space back at old location:
-                        disk[j] = disk.remove(i);
-                        disk.insert(i, DiskFragment::Free(file_size));
-                        if file_size < free_size {
-                            // when there was room over, put some remaining free space
-                            disk.insert(j + 1, DiskFragment::Free(free_size - file_size));
-                        }
-                        break 'replace;
-                    }
-                    // continue searching, not enough space to fit
+    for (file_offset, file_size) in files.iter_mut().rev() {
+        // the leftmost span that fits is the leftmost of the tops of the heaps that are big enough
+        let best_fit = (*file_size..10)
+            .filter_map(|size| Some((free_spans[size].peek()?.0, size)))
+            .min();
+        if let Some((free_offset, free_size)) = best_fit {
+            // files only move to the left
+            if free_offset < *file_offset {
+                free_spans[free_size].pop();
+                *file_offset = free_offset;
+                if free_size > *file_size {
+                    // when there was room over, put the remaining free space back.
+                    // the space the file leaves behind is never used, only files to the left of it are left to move.
+                    free_spans[free_size - *file_size].push(Reverse(free_offset + *file_size));
                 }
             }
         }
     }
 
-    calculate_checksum_on_fragments(disk.as_slice())
+    files
+        .iter()
+        .enumerate()
+        .map(|(id, &(offset, size))| add_blocks_to_checksum(id, offset, size))
+        .sum()
 }
 
-fn calculate_checksum_on_fragments(disk: &[DiskFragment]) -> usize {
-    let mut checksum: usize = 0;
-    let mut i: usize = 0;
-    for file in disk {
-        match file {
-            DiskFragment::File(id, size) => {
-                for _ in 0..*size {
-                    checksum += id * i;
-                    i += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2333133121414131402\n";
+
+    // The whole-file compaction as it was before the free spans were kept in heaps:
+    // a list of files and free spans, where each file is moved into the first free span that fits.
+    fn move_complete_files_in_list(input: &str) -> usize {
+        let mut disk: Vec<(Option<usize>, usize)> = input
+            .trim_end()
+            .char_indices()
+            .map(|(index, number)| {
+                let size = number.to_digit(10).unwrap() as usize;
+                ((index % 2 == 0).then_some(index / 2), size)
+            })
+            .collect();
+        for i in (0..disk.len()).rev() {
+            let (Some(_), file_size) = disk[i] else {
+                continue;
+            };
+            let fits = (0..i).find(|&j| disk[j].0.is_none() && disk[j].1 >= file_size);
+            if let Some(j) = fits {
+                let free_size = disk[j].1;
+                disk[j] = disk.remove(i);
+                disk.insert(i, (None, file_size));
+                if file_size < free_size {
+                    disk.insert(j + 1, (None, free_size - file_size));
                 }
             }
-            DiskFragment::Free(size) => {
-                i += *size as usize;
+        }
+        let mut position = 0;
+        let mut checksum = 0;
+        for (id, size) in disk {
+            if let Some(id) = id {
+                checksum += add_blocks_to_checksum(id, position, size);
             }
+            position += size;
+        }
+        checksum
+    }
+
+    #[test]
+    fn blocks_of_the_example() {
+        assert_eq!(move_file_blocks_to_front(EXAMPLE), 1928);
+    }
+
+    #[test]
+    fn blocks_edge_cases() {
+        for (input, checksum) in [("0312032", 5), ("123454", 60), ("5", 0), ("53", 0)] {
+            assert_eq!(move_file_blocks_to_front(input), checksum, "{input}");
+        }
+    }
+
+    #[test]
+    fn whole_files_of_the_example() {
+        assert_eq!(move_complete_files_to_front(EXAMPLE), 2858);
+    }
+
+    #[test]
+    fn whole_files_edge_cases() {
+        // zero-length files, trailing free space, and a single file (with and without free space after it)
+        for (input, checksum) in [("0312032", 5), ("123454", 132), ("5", 0), ("53", 0)] {
+            assert_eq!(move_complete_files_to_front(input), checksum, "{input}");
+        }
+    }
+
+    #[test]
+    fn whole_files_like_the_list_compaction() {
+        // a simple pseudo-random generator, so the disk maps are the same on every run
+        let mut seed = 0x2024_u64;
+        for _ in 0..1000 {
+            let length = 1 + seed % 25;
+            let input: String = (0..length)
+                .map(|_| {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    char::from(b'0' + (seed >> 33) as u8 % 10)
+                })
+                .collect();
+            assert_eq!(
+                move_complete_files_to_front(&input),
+                move_complete_files_in_list(&input),
+                "{input}"
+            );
         }
     }
-    checksum
 }
@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+use std::io::{Read, Seek, SeekFrom};
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Part 1 for disk maps that don't fit in memory: the map is read in chunks from both ends at the same time.
+/// That takes a reader that can seek, like a file, so it doesn't work on a pipe or stdin.
+pub fn move_file_blocks_to_front_from_reader<R: Read + Seek>(reader: R) -> std::io::Result<usize> {
+    let reader = RefCell::new(reader);
+    let mut length = reader.borrow_mut().seek(SeekFrom::End(0))?;
+
+    let mut back = ChunkedDigits::new(&reader, length);
+    // ignore a trailing newline (or any other whitespace) at the end of the file
+    while length > 0 && !back.read_at(length - 1)?.is_ascii_digit() {
+        length -= 1;
+    }
+    let mut front = ChunkedDigits::new(&reader, length);
+
+    let mut front_position = 0;
+    let mut back_position = length;
+    crate::compact_blocks(
+        length as usize,
+        || {
+            front_position += 1;
+            Ok(front.read_at(front_position - 1)? - b'0')
+        },
+        || {
+            back_position -= 1;
+            Ok(back.read_at(back_position)? - b'0')
+        },
+    )
+}
+
+/// Keeps one chunk of the file in memory, and loads the chunk around a position when it's needed.
+struct ChunkedDigits<'a, R> {
+    reader: &'a RefCell<R>,
+    length: u64,
+    buffer: Vec<u8>,
+    buffer_start: u64,
+}
+
+impl<'a, R: Read + Seek> ChunkedDigits<'a, R> {
+    fn new(reader: &'a RefCell<R>, length: u64) -> ChunkedDigits<'a, R> {
+        ChunkedDigits {
+            reader,
+            length,
+            buffer: Vec::new(),
+            buffer_start: 0,
+        }
+    }
+
+    fn read_at(&mut self, position: u64) -> std::io::Result<u8> {
+        let in_buffer = position >= self.buffer_start
+            && position < self.buffer_start + self.buffer.len() as u64;
+        if !in_buffer {
+            // align to chunks: the front cursor reads ahead, the back cursor ends up reading the chunk before it
+            self.buffer_start = position - position % CHUNK_SIZE;
+            let size = CHUNK_SIZE.min(self.length - self.buffer_start) as usize;
+            self.buffer.resize(size, 0);
+            let mut reader = self.reader.borrow_mut();
+            reader.seek(SeekFrom::Start(self.buffer_start))?;
+            reader.read_exact(&mut self.buffer)?;
+        }
+        Ok(self.buffer[(position - self.buffer_start) as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn streamed_like_in_memory() {
+        for input in ["2333133121414131402\n", "0312032", "123454", "5", "53", ""] {
+            let streamed = move_file_blocks_to_front_from_reader(Cursor::new(input)).unwrap();
+            assert_eq!(streamed, crate::move_file_blocks_to_front(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn streamed_over_several_chunks() {
+        let input: String = (0..3 * CHUNK_SIZE + 7)
+            .map(|i| char::from(b'0' + (i * 7 % 10) as u8))
+            .collect();
+        let streamed = move_file_blocks_to_front_from_reader(Cursor::new(&input)).unwrap();
+        assert_eq!(streamed, crate::move_file_blocks_to_front(&input));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::engine::{Blink, RuleSet};
+use std::collections::{HashMap, HashSet};
+
+/// Counts the stones a single stone turns into, remembering every (stone, blinks) it has seen.
+/// Keep the counter around to share that work between calls, like part 1 and part 2.
+pub struct StoneCounter<R: RuleSet> {
+    rules: R,
+    // None means the count (or an engraving on the way) doesn't fit in a u64
+    memo: HashMap<(u64, usize), Option<u64>>,
+}
+
+impl<R: RuleSet> StoneCounter<R> {
+    pub fn new(rules: R) -> StoneCounter<R> {
+        StoneCounter {
+            rules,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// Works one blink at a time instead of recursing, so any number of blinks fits on the stack.
+    pub fn count(&mut self, stone: u64, blinks: usize) -> Option<u64> {
+        if blinks == 0 {
+            return Some(1);
+        }
+        // going down: the distinct stones after every blink, as long as their count isn't known yet
+        let mut layers: Vec<Vec<u64>> = Vec::from([Vec::from([stone])]);
+        for remaining in (2..=blinks).rev() {
+            let mut next = HashSet::new();
+            for &stone in layers.last().unwrap() {
+                if self.memo.contains_key(&(stone, remaining)) {
+                    continue;
+                }
+                match self.rules.blink(stone) {
+                    Some(Blink::One(new_stone)) => {
+                        next.insert(new_stone);
+                    }
+                    Some(Blink::Two(left, right)) => {
+                        next.insert(left);
+                        next.insert(right);
+                    }
+                    None => {}
+                }
+            }
+            layers.push(next.into_iter().collect());
+        }
+
+        // going up: count every layer from the counts of the layer below it
+        for (depth, layer) in layers.iter().enumerate().rev() {
+            let remaining = blinks - depth;
+            for &stone in layer {
+                if self.memo.contains_key(&(stone, remaining)) {
+                    continue;
+                }
+                let count = |stone: u64| match remaining - 1 {
+                    0 => Some(1),
+                    below => self.memo[&(stone, below)],
+                };
+                let result = match self.rules.blink(stone) {
+                    Some(Blink::One(new_stone)) => count(new_stone),
+                    Some(Blink::Two(left, right)) => count(left)
+                        .zip(count(right))
+                        .and_then(|(left, right)| left.checked_add(right)),
+                    None => None,
+                };
+                self.memo.insert((stone, remaining), result);
+            }
+        }
+        self.memo[&(stone, blinks)]
+    }
+
+    pub fn count_all(&mut self, stones: &[u64], blinks: usize) -> Option<u64> {
+        stones.iter().try_fold(0u64, |total, &stone| {
+            total.checked_add(self.count(stone, blinks)?)
+        })
+    }
+}
+
+/// All stones that can ever appear when starting with a set of stones, and how they turn into each other.
+pub struct ClosedSet {
+    stones: Vec<u64>,
+    index: HashMap<u64, usize>,
+    // for each stone, the stones it turns into (an index can be there twice, like 1000 -> 10 and 0 -> 10 and 0)
+    transitions: Vec<Vec<usize>>,
+    // stones that can be reached from a loop, after a while only these stones are left
+    core: Vec<bool>,
+}
+
+pub struct GrowthReport {
+    // the number of distinct stones after each blink, starting before the first blink
+    pub distinct_per_blink: Vec<usize>,
+    // the number of distinct stones seen so far, after each blink
+    pub seen_per_blink: Vec<usize>,
+    // the first blink at which every stone of the closed set has been seen, no new stones show up after it
+    pub saturated_at: usize,
+    // the first blink at which only stones in the core are left
+    pub core_reached_at: usize,
+}
+
+impl ClosedSet {
+    /// Returns None when an engraving doesn't fit in a u64.
+    pub fn find<R: RuleSet>(rules: &R, initial_stones: &[u64]) -> Option<ClosedSet> {
+        let mut closed_set = ClosedSet {
+            stones: Vec::new(),
+            index: HashMap::new(),
+            transitions: Vec::new(),
+            core: Vec::new(),
+        };
+        let mut queue: Vec<_> = initial_stones
+            .iter()
+            .map(|&stone| closed_set.add_stone(stone))
+            .collect();
+        while let Some(i) = queue.pop() {
+            if !closed_set.transitions[i].is_empty() {
+                continue; // already processed, a stone can be in the queue more than once
+            }
+            let next_stones = match rules.blink(closed_set.stones[i])? {
+                Blink::One(stone) => Vec::from([stone]),
+                Blink::Two(left, right) => Vec::from([left, right]),
+            };
+            for stone in next_stones {
+                let is_new = !closed_set.index.contains_key(&stone);
+                let j = closed_set.add_stone(stone);
+                closed_set.transitions[i].push(j);
+                if is_new {
+                    queue.push(j);
+                }
+            }
+        }
+        closed_set.find_core();
+        Some(closed_set)
+    }
+
+    pub fn len(&self) -> usize {
+        self.stones.len()
+    }
+
+    pub fn core_size(&self) -> usize {
+        self.core.iter().filter(|&&c| c).count()
+    }
+
+    fn add_stone(&mut self, stone: u64) -> usize {
+        *self.index.entry(stone).or_insert_with(|| {
+            self.stones.push(stone);
+            self.transitions.push(Vec::new());
+            self.stones.len() - 1
+        })
+    }
+
+    fn find_core(&mut self) {
+        // peel off stones that nothing turns into, until only stones that come out of a loop remain (Kahn's algorithm)
+        let mut incoming = vec![0; self.len()];
+        for transitions in self.transitions.iter() {
+            for &j in transitions {
+                incoming[j] += 1;
+            }
+        }
+        let mut queue: Vec<_> = (0..self.len()).filter(|&i| incoming[i] == 0).collect();
+        self.core = vec![true; self.len()];
+        while let Some(i) = queue.pop() {
+            self.core[i] = false;
+            for &j in self.transitions[i].iter() {
+                incoming[j] -= 1;
+                if incoming[j] == 0 {
+                    queue.push(j);
+                }
+            }
+        }
+    }
+
+    fn indexes(&self, stones: &[u64]) -> Vec<usize> {
+        stones.iter().map(|stone| self.index[stone]).collect()
+    }
+
+    pub fn growth(&self, initial_stones: &[u64]) -> GrowthReport {
+        let mut current: HashSet<usize> = self.indexes(initial_stones).into_iter().collect();
+        let mut seen = current.clone();
+        let mut distinct_per_blink = Vec::from([current.len()]);
+        let mut seen_per_blink = Vec::from([seen.len()]);
+
+        // keep blinking until every stone of the closed set was seen, and only core stones are left
+        let only_core = |current: &HashSet<usize>| current.iter().all(|&i| self.core[i]);
+        let mut saturated_at = (seen.len() == self.len()).then_some(0);
+        let mut core_reached_at = only_core(&current).then_some(0);
+        let mut blink = 0;
+        while saturated_at.is_none() || core_reached_at.is_none() {
+            current = current
+                .iter()
+                .flat_map(|&i| self.transitions[i].iter().copied())
+                .collect();
+            seen.extend(current.iter().copied());
+            blink += 1;
+
+            distinct_per_blink.push(current.len());
+            seen_per_blink.push(seen.len());
+            if saturated_at.is_none() && seen.len() == self.len() {
+                saturated_at = Some(blink);
+            }
+            if core_reached_at.is_none() && only_core(&current) {
+                core_reached_at = Some(blink);
+            }
+        }
+
+        GrowthReport {
+            distinct_per_blink,
+            seen_per_blink,
+            saturated_at: saturated_at.unwrap(),
+            core_reached_at: core_reached_at.unwrap(),
+        }
+    }
+
+    /// Counts the stones after any number of blinks, modulo `modulus` because the real number gets huge.
+    /// First blinks until only core stones are left, then uses matrix exponentiation on the core,
+    /// unless the core is so big that blinking the remaining blinks one by one is cheaper.
+    pub fn count_modulo(&self, initial_stones: &[u64], blinks: u64, modulus: u64) -> u64 {
+        // two values below the modulus can be added without overflowing
+        assert!(
+            modulus > 0 && modulus <= 1 << 63,
+            "modulus must be between 1 and 2^63"
+        );
+        let mut counts = vec![0u64; self.len()];
+        for i in self.indexes(initial_stones) {
+            counts[i] = (counts[i] + 1) % modulus;
+        }
+
+        // step by step, until all stones are in the core
+        let mut remaining_blinks = blinks;
+        while remaining_blinks > 0 && (0..self.len()).any(|i| counts[i] > 0 && !self.core[i]) {
+            counts = self.step_modulo(&counts, modulus);
+            remaining_blinks -= 1;
+        }
+
+        // a multiplication of two core matrices costs size^3, and pow needs up to two of them per bit
+        let core_indexes: Vec<_> = (0..self.len()).filter(|&i| self.core[i]).collect();
+        let size = core_indexes.len() as u128;
+        let edges: usize = core_indexes
+            .iter()
+            .map(|&i| self.transitions[i].len())
+            .sum();
+        let bits = (u64::BITS - remaining_blinks.leading_zeros()) as u128;
+        if remaining_blinks as u128 * edges as u128 <= 2 * bits * size * size * size {
+            for _ in 0..remaining_blinks {
+                counts = self.step_modulo(&counts, modulus);
+            }
+            return counts.iter().fold(0, |sum, &count| (sum + count) % modulus);
+        }
+
+        // the core is closed: a core stone only turns into core stones
+        let position: HashMap<_, _> = core_indexes
+            .iter()
+            .enumerate()
+            .map(|(p, &i)| (i, p))
+            .collect();
+        let size = core_indexes.len();
+        let mut matrix = Matrix::zero(size);
+        for (p, &i) in core_indexes.iter().enumerate() {
+            for j in self.transitions[i].iter() {
+                let q = position[j];
+                matrix.values[p * size + q] = (matrix.values[p * size + q] + 1) % modulus;
+            }
+        }
+
+        // (matrix^n)[p][q] is the number of stones q a single stone p turns into after n blinks
+        let power = matrix.pow(remaining_blinks, modulus);
+        let mut total = 0u64;
+        for (p, &i) in core_indexes.iter().enumerate() {
+            let stones_from_p = power.values[p * size..(p + 1) * size]
+                .iter()
+                .fold(0, |sum, &value| (sum + value) % modulus);
+            total = (total + mul_mod(counts[i], stones_from_p, modulus)) % modulus;
+        }
+        total
+    }
+
+    fn step_modulo(&self, counts: &[u64], modulus: u64) -> Vec<u64> {
+        let mut next_counts = vec![0u64; self.len()];
+        for (i, &count) in counts.iter().enumerate() {
+            for &j in self.transitions[i].iter() {
+                next_counts[j] = (next_counts[j] + count) % modulus;
+            }
+        }
+        next_counts
+    }
+}
+
+#[derive(Clone)]
+struct Matrix {
+    size: usize,
+    values: Vec<u64>,
+}
+
+impl Matrix {
+    fn zero(size: usize) -> Matrix {
+        Matrix {
+            size,
+            values: vec![0; size * size],
+        }
+    }
+
+    fn identity(size: usize, modulus: u64) -> Matrix {
+        let mut matrix = Matrix::zero(size);
+        for i in 0..size {
+            matrix.values[i * size + i] = 1 % modulus;
+        }
+        matrix
+    }
+
+    fn multiply(&self, other: &Matrix, modulus: u64) -> Matrix {
+        let size = self.size;
+        let mut result = Matrix::zero(size);
+        for i in 0..size {
+            for k in 0..size {
+                let a = self.values[i * size + k];
+                if a == 0 {
+                    continue;
+                }
+                for j in 0..size {
+                    let cell = &mut result.values[i * size + j];
+                    *cell = (*cell + mul_mod(a, other.values[k * size + j], modulus)) % modulus;
+                }
+            }
+        }
+        result
+    }
+
+    fn pow(&self, exponent: u64, modulus: u64) -> Matrix {
+        // exponentiation by squaring
+        let mut result = Matrix::identity(self.size, modulus);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result.multiply(&base, modulus);
+            }
+            base = base.multiply(&base, modulus);
+            exponent /= 2;
+        }
+        result
+    }
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    ((a as u128 * b as u128) % modulus as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::PlutonianRules;
+
+    const EXAMPLE: [u64; 2] = [125, 17];
+
+    #[test]
+    fn counts_of_the_example() {
+        let mut counter = StoneCounter::new(PlutonianRules);
+        assert_eq!(counter.count_all(&EXAMPLE, 6), Some(22));
+        assert_eq!(counter.count_all(&EXAMPLE, 25), Some(55312));
+        assert_eq!(counter.count_all(&EXAMPLE, 75), Some(65601038650482));
+    }
+
+    #[test]
+    fn many_blinks_fit_on_the_stack() {
+        // far more stones than a u64 can count, but it has to get there without a stack overflow
+        let mut counter = StoneCounter::new(PlutonianRules);
+        assert_eq!(counter.count(0, 10_000), None);
+    }
+
+    #[test]
+    fn closed_set_and_core() {
+        // 0 turns into 1, 2024, 20 and 24, 2, 0, 2 and 4, ... and comes back, so everything is in the core
+        let closed_set = ClosedSet::find(&PlutonianRules, &[0]).unwrap();
+        assert_eq!((closed_set.len(), closed_set.core_size()), (54, 54));
+        let growth = closed_set.growth(&[0]);
+        assert_eq!((growth.saturated_at, growth.core_reached_at), (16, 0));
+
+        // 125 and 17 never come back, it takes 11 blinks before only core stones are left
+        let closed_set = ClosedSet::find(&PlutonianRules, &EXAMPLE).unwrap();
+        assert_eq!((closed_set.len(), closed_set.core_size()), (76, 54));
+        let growth = closed_set.growth(&EXAMPLE);
+        assert_eq!((growth.saturated_at, growth.core_reached_at), (14, 11));
+        assert_eq!(growth.distinct_per_blink[0], 2);
+    }
+
+    #[test]
+    fn count_modulo_like_the_counter() {
+        let closed_set = ClosedSet::find(&PlutonianRules, &EXAMPLE).unwrap();
+        assert_eq!(closed_set.count_modulo(&EXAMPLE, 25, 1 << 63), 55312);
+        assert_eq!(
+            closed_set.count_modulo(&EXAMPLE, 75, 1_000_000_007),
+            65601038650482 % 1_000_000_007
+        );
+    }
+}
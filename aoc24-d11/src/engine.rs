@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// What a single stone turns into after a blink.
+pub enum Blink {
+    One(u64),
+    Two(u64, u64),
+}
+
+pub trait RuleSet {
+    /// Returns None when the new engraving doesn't fit in a u64.
+    fn blink(&self, stone: u64) -> Option<Blink>;
+}
+
+/// The rules from the puzzle, applied in order:
+/// 0 becomes 1, an even number of digits splits the stone in two halves, anything else is multiplied by 2024.
+pub struct PlutonianRules;
+
+impl RuleSet for PlutonianRules {
+    fn blink(&self, stone: u64) -> Option<Blink> {
+        if stone == 0 {
+            return Some(Blink::One(1));
+        }
+        let digits = stone.ilog10() + 1;
+        if digits.is_multiple_of(2) {
+            // leading zeros on the right half disappear automatically, because it's a number
+            let half = 10u64.pow(digits / 2);
+            Some(Blink::Two(stone / half, stone % half))
+        } else {
+            Some(Blink::One(stone.checked_mul(2024)?))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BlinkError {
+    // the engraving of a stone got too big for a u64
+    StoneOverflow { blink: usize, stone: u64 },
+    // there are more stones than fit in a u64
+    CountOverflow { blink: usize },
+}
+
+impl Display for BlinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlinkError::StoneOverflow { blink, stone } => {
+                write!(f, "stone {stone} overflows at blink {blink}")
+            }
+            BlinkError::CountOverflow { blink } => {
+                write!(f, "the number of stones overflows at blink {blink}")
+            }
+        }
+    }
+}
+
+pub struct BlinkSummary {
+    pub blink: usize,
+    pub distinct_stones: usize,
+    pub total_stones: u64,
+}
+
+/// Keeps track of how many stones there are with each engraving, the order of the stones doesn't matter for the count.
+pub struct StoneEngine<R: RuleSet> {
+    rules: R,
+    stones: HashMap<u64, u64>,
+    history: Vec<BlinkSummary>,
+}
+
+impl<R: RuleSet> StoneEngine<R> {
+    pub fn new(rules: R, stones: &[u64]) -> StoneEngine<R> {
+        let mut counts = HashMap::new();
+        for &stone in stones {
+            *counts.entry(stone).or_default() += 1;
+        }
+        let mut engine = StoneEngine {
+            rules,
+            stones: counts,
+            history: Vec::new(),
+        };
+        engine.history.push(BlinkSummary {
+            blink: 0,
+            distinct_stones: engine.stones.len(),
+            total_stones: stones.len() as u64,
+        });
+        engine
+    }
+
+    pub fn parse(rules: R, input: &str) -> StoneEngine<R> {
+        let stones: Vec<u64> = input
+            .split_whitespace()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        StoneEngine::new(rules, &stones)
+    }
+
+    pub fn blink(&mut self) -> Result<(), BlinkError> {
+        let blink = self.history.len();
+        let mut result: HashMap<u64, u64> = HashMap::with_capacity(self.stones.len());
+        let mut add = |stone: u64, count: u64| -> Result<(), BlinkError> {
+            let total = result.entry(stone).or_default();
+            *total = total
+                .checked_add(count)
+                .ok_or(BlinkError::CountOverflow { blink })?;
+            Ok(())
+        };
+
+        for (&stone, &count) in self.stones.iter() {
+            match self.rules.blink(stone) {
+                Some(Blink::One(new_stone)) => add(new_stone, count)?,
+                Some(Blink::Two(left, right)) => {
+                    add(left, count)?;
+                    add(right, count)?;
+                }
+                None => return Err(BlinkError::StoneOverflow { blink, stone }),
+            }
+        }
+
+        let total_stones = result
+            .values()
+            .try_fold(0u64, |total, &count| total.checked_add(count))
+            .ok_or(BlinkError::CountOverflow { blink })?;
+        self.history.push(BlinkSummary {
+            blink,
+            distinct_stones: result.len(),
+            total_stones,
+        });
+        self.stones = result;
+        Ok(())
+    }
+
+    pub fn blink_times(&mut self, number_of_blinks: usize) -> Result<u64, BlinkError> {
+        for _ in 0..number_of_blinks {
+            self.blink()?;
+        }
+        Ok(self.total_stones())
+    }
+
+    pub fn total_stones(&self) -> u64 {
+        self.history.last().unwrap().total_stones
+    }
+
+    /// The number of stones for each engraving, sorted by engraving.
+    pub fn distribution(&self) -> Vec<(u64, u64)> {
+        let mut distribution: Vec<_> = self.stones.iter().map(|(&s, &c)| (s, c)).collect();
+        distribution.sort();
+        distribution
+    }
+
+    /// The number of distinct and total stones, from before the first blink up to the current blink.
+    pub fn history(&self) -> &[BlinkSummary] {
+        &self.history
+    }
+}
@@ -1,7 +1,11 @@
-﻿use aoc24_tools::*;
-use std::collections::{HashMap, VecDeque};
+﻿use analysis::{ClosedSet, StoneCounter};
+use aoc24_tools::*;
+use engine::{PlutonianRules, StoneEngine};
 use std::fs::read_to_string;
 
+mod analysis;
+mod engine;
+
 const DAY: u8 = 11;
 
 fn main() {
@@ -15,123 +19,77 @@ fn main() {
     println!("Number of stones after 75 blinks (Part 2): {part2}");
 
     print_summary(DAY);
-}
 
-fn solve(input: &str) -> (usize, usize) {
-    let part1 = measure!(
-        {
-            let stones = parse_as_vector(input);
-            brute_force_blinking(&stones, 25)
-        },
-        "brute-25x"
-    );
-    let _ = measure!(
-        {
-            let stones = parse_as_hashmap(input);
-            smarter_blinking(stones, 25)
-        },
-        "smart-25x"
-    );
-    let part2 = measure!(
-        {
-            let stones = parse_as_hashmap(input);
-            smarter_blinking(stones, 75)
-        },
-        "smart-75x"
-    );
-    (part1, part2)
-}
+    // optional: cargo run -r -- --blinks 100 [--distribution]
+    if let Some(blinks) = get_argument("blinks") {
+        print_blinks(&data, blinks.parse().unwrap(), has_flag("distribution"));
+    }
 
-fn parse_as_vector(input: &str) -> Vec<&str> {
-    input.split_whitespace().into_iter().collect()
+    // optional: cargo run -r -- --analyse [--many-blinks 5000] [--modulus 1000000007]
+    if has_flag("analyse") {
+        print_analysis(&data);
+    }
 }
 
-fn brute_force_blinking(stones: &Vec<&str>, number_of_blinks: u8) -> usize {
-    // put all stones in a queue with the amount of remaining blinks for that stone
-    let mut queue = stones
+fn print_analysis(input: &str) {
+    let stones = parse(input);
+    let closed_set = ClosedSet::find(&PlutonianRules, &stones).expect("An engraving overflows");
+    let growth = closed_set.growth(&stones);
+    println!(
+        "{} distinct stones can ever appear, {} of them keep coming back (the core)",
+        closed_set.len(),
+        closed_set.core_size()
+    );
+    for (blink, (distinct, seen)) in growth
+        .distinct_per_blink
         .iter()
-        .map(|&s| (s.to_string(), number_of_blinks))
-        .collect::<VecDeque<_>>();
-
-    let mut result = 0;
-    // keep working the queue until empty (with a lot of repeating work...)
-    while let Some((engraving, remaining_blinks)) = queue.pop_front() {
-        if remaining_blinks == 0 {
-            result += 1;
-        } else {
-            blink_single_stone_using_queue(&mut queue, engraving, remaining_blinks - 1);
-        }
+        .zip(growth.seen_per_blink.iter())
+        .enumerate()
+    {
+        println!("After {blink:3} blinks: {distinct:5} distinct stones, {seen:5} seen so far");
     }
+    println!(
+        "No new stones after blink {}, only core stones after blink {}",
+        growth.saturated_at, growth.core_reached_at
+    );
 
-    result
-}
-
-fn blink_single_stone_using_queue(
-    queue: &mut VecDeque<(String, u8)>,
-    engraving: String,
-    blinks: u8,
-) {
-    if engraving == "0" || engraving == "" {
-        queue.push_back(("1".to_string(), blinks));
-    } else if engraving.len() % 2 == 0 {
-        let half = engraving.len() / 2;
-        let left_stone = engraving[..half].to_string();
-        let right_stone = engraving[half..].trim_start_matches('0').to_string();
-        queue.push_back((left_stone, blinks));
-        queue.push_back((right_stone, blinks));
-    } else {
-        let value = engraving.parse::<u64>().unwrap() * 2024;
-        queue.push_back((value.to_string(), blinks));
-    }
+    let blinks = get_argument("many-blinks").map_or(1000, |b| b.parse().unwrap());
+    let modulus = get_argument("modulus").map_or(1_000_000_007, |m| m.parse().unwrap());
+    let count = closed_set.count_modulo(&stones, blinks, modulus);
+    println!("Number of stones after {blinks} blinks, modulo {modulus}: {count}");
 }
 
-fn parse_as_hashmap(data: &str) -> HashMap<String, usize> {
-    let mut stones = HashMap::new();
-    for value in data.split_whitespace() {
-        stones
-            .entry(value.to_string())
-            .and_modify(|count| *count += 1)
-            .or_insert(1);
+fn print_blinks(input: &str, number_of_blinks: usize, show_distribution: bool) {
+    let mut engine = StoneEngine::parse(PlutonianRules, input);
+    let result = engine.blink_times(number_of_blinks);
+    for summary in engine.history() {
+        println!(
+            "After {:3} blinks: {:5} distinct stones, {} stones in total",
+            summary.blink, summary.distinct_stones, summary.total_stones
+        );
     }
-    stones
-}
-
-fn smarter_blinking(stones: HashMap<String, usize>, number_of_blinks: u8) -> usize {
-    let mut stones = stones;
-    for _ in 0..number_of_blinks {
-        stones = blink_hashmap_of_stones(&stones);
+    if let Err(error) = result {
+        println!("Stopped: {error}");
     }
-
-    stones.values().sum()
-}
-
-fn blink_hashmap_of_stones(stones: &HashMap<String, usize>) -> HashMap<String, usize> {
-    let mut result = HashMap::with_capacity(stones.len());
-
-    for (engraving, count) in stones {
-        let new_stones = blink_single_stone(engraving, *count);
-        for (new_engraving, new_count) in new_stones {
-            result
-                .entry(new_engraving)
-                .and_modify(|count| *count += new_count)
-                .or_insert(new_count);
+    if show_distribution {
+        for (stone, count) in engine.distribution() {
+            println!("{stone:>20}: {count}");
         }
     }
-
-    result
 }
 
-fn blink_single_stone(engraved_number: &String, count: usize) -> Vec<(String, usize)> {
-    if engraved_number == "0" || engraved_number == "" {
-        Vec::from([("1".to_string(), count)])
-    } else if engraved_number.len() % 2 == 0 {
-        let half = engraved_number.len() / 2;
-        let left_stone = engraved_number[..half].to_string();
-        let right_stone = engraved_number[half..].trim_start_matches('0').to_string();
+fn solve(input: &str) -> (u64, u64) {
+    let stones = measure!({ parse(input) }, "parse");
+    // the counter remembers what it has counted, so part 2 can re-use the work done for part 1
+    let mut counter = StoneCounter::new(PlutonianRules);
+    let part1 = measure!({ counter.count_all(&stones, 25).unwrap() }, "part1");
+    let part2 = measure!({ counter.count_all(&stones, 75).unwrap() }, "part2");
+    (part1, part2)
+}
 
-        Vec::from([(left_stone, count), (right_stone, count)])
-    } else {
-        let value = engraved_number.parse::<u64>().unwrap() * 2024;
-        Vec::from([(value.to_string(), count)])
-    }
-}
\ No newline at end of file
+fn parse(input: &str) -> Vec<u64> {
+    input
+        .split_whitespace()
+        .map(|s| s.parse().unwrap())
+        .collect()
+}
@@ -1,8 +1,10 @@
 ﻿use aoc24_tools::*;
-use std::collections::{HashSet, VecDeque};
+use regions::{label_regions, Garden};
 use std::fs::read_to_string;
 use std::str::FromStr;
 
+mod regions;
+
 const DAY: u8 = 12;
 
 fn main() {
@@ -16,113 +18,57 @@ fn main() {
     println!("Price of fencing with bulk discount (Part 2): {part2}");
 
     print_summary(DAY);
-}
-
-fn solve(input: &str) -> (usize, usize) {
-    let mut part1 = 0;
-    let mut part2 = 0;
-    let map = Map::from_str(input).unwrap();
 
-    let mut visited = HashSet::new();
-    for y in 0..map.rows {
-        for x in 0..map.cols {
-            let point = Point::new(x as i16, y as i16);
-            if !visited.contains(&point) {
-                let (area, perimeter, region) = calculate_region(&map, point);
-                part1 += perimeter * area;
-                if area > 2 {
-                    let discount = calculate_bulk_discount(&region);
-                    let number_of_sides = perimeter - discount;
-                    part2 += number_of_sides * area;
-                } else { // for 1 or 2 squares the perimeter is always 4:
-                    part2 += 4 * area;
-                }
-
-                region.into_iter().for_each(|point| {
-                    visited.insert(point);
-                });
-            }
-        }
+    // optional: cargo run -r -- --regions [--render]
+    if has_flag("regions") {
+        print_regions(&data, has_flag("render"));
     }
-    (part1, part2)
 }
 
-fn calculate_bulk_discount(region: &HashSet<Point>) -> usize {
-    let mut no_corner = 0;
-    // scan over the 'shadowed' region, always look at a block of 2x2
-    // when exactly two are in within a block, this is a straight fence and not a corner
-    // examples: oo  xo
-    //           xx  xo
-    // except for checkerboard pattern
-    // like: xo ox
-    //       ox xo
-    // counting and using xor is an option, but pattern matching is faster...
-
-    let mut queue = region
-        .iter()
-        .map(|p| (p.clone(), false)) //boolean: not a shadow
-        .collect::<VecDeque<(Point, bool)>>();
-
-    while let Some((point, is_shadow)) = queue.pop_front() {
-        //for point in unique_with_shadow {
-        let x = point.x;
-        let y = point.y;
-
-        let top_left = Point::new(x - 1, y - 1);
-        let top_right = Point::new(x - 0, y - 1);
-        let bottom_left = Point::new(x - 1, y - 0);
-        let bottom_right = Point::new(x + 0, y + 0);
-
-        let mut area_bits = !region.contains(&top_left) as u8;
-        area_bits |= (!region.contains(&top_right) as u8) << 1;
-        area_bits |= (!region.contains(&bottom_left) as u8) << 2;
-        area_bits |= (!region.contains(&bottom_right) as u8) << 3;
-
-        if area_bits == 0b0000_1100
-            || area_bits == 0b0000_0011
-            || area_bits == 0b0000_1010
-            || area_bits == 0b0000_0101
-        {
-            no_corner += 1;
-        }
-
-        if !is_shadow {
-            let shadow = Point::new(x + 1, y + 1);
-            if !region.contains(&shadow) {
-                queue.push_back((shadow, true));
-            }
+fn print_regions(input: &str, render: bool) {
+    let garden = Garden::new(&Map::from_str(input).unwrap());
+    for region in garden.regions() {
+        let bounding_box = &region.bounding_box;
+        print!(
+            "Region {:4} ({}): area {:4}, perimeter {:4}, {:4} sides, box ({},{})-({},{}), {} holes",
+            region.id,
+            region.plant,
+            region.area,
+            region.perimeter,
+            region.sides,
+            bounding_box.left,
+            bounding_box.top,
+            bounding_box.right,
+            bounding_box.bottom,
+            region.holes
+        );
+        match region.enclosing_region.and_then(|id| garden.region(id)) {
+            Some(outer) => println!(", inside region {} ({})", outer.id, outer.plant),
+            None => println!(),
         }
     }
-
-    no_corner
+    let price: usize = garden.regions().map(|region| region.price()).sum();
+    let discounted_price: usize = garden
+        .regions()
+        .map(|region| region.discounted_price())
+        .sum();
+    println!("Total price {price}, with bulk discount {discounted_price}");
+    if render {
+        garden.print();
+    }
 }
 
-fn calculate_region(map: &Map, start: Point) -> (usize, usize, HashSet<Point>) {
-    let mut area = 0;
-    let mut perimeter = 0;
-    let mut known_region: HashSet<Point> = HashSet::new();
-
-    let character = map.get(&start).unwrap();
-    let mut queue = VecDeque::from([(start, character)]);
-
-    while let Some((point, character)) = queue.pop_front() {
-        // add to region, if not yet processed before
-        if !known_region.contains(&point) {
-            for neighbour_point in point.get_neighbours() {
-                if let Some(neighbour_char) = map.get(&neighbour_point) {
-                    if neighbour_char == character {
-                        queue.push_back((neighbour_point, neighbour_char)); //add to search
-                        continue;
-                    }
-                }
-                // not the same region or out of bounds, so increase perimeter
-                perimeter += 1;
-            }
-            known_region.insert(point); // move ownership here
-            area += 1;
-        }
-    }
-    (area, perimeter, known_region)
+fn solve(input: &str) -> (usize, usize) {
+    let map = Map::from_str(input).unwrap();
+    label_regions(&map)
+        .sizes
+        .iter()
+        .fold((0, 0), |(part1, part2), region| {
+            (
+                part1 + region.area * region.perimeter,
+                part2 + region.area * region.sides,
+            )
+        })
 }
 
 struct Map {
@@ -136,16 +82,11 @@ impl Map {
         Map { data, cols, rows }
     }
 
-    fn get(&self, p: &Point) -> Option<&char> {
-        if p.x > -1 && p.y > -1 {
-            let x = p.x as usize;
-            let y = p.y as usize;
-            if y < self.rows && x < self.cols {
-                let index = y * self.cols + x;
-                return Some(&self.data[index]);
-            }
+    fn get(&self, x: isize, y: isize) -> Option<char> {
+        if x < 0 || y < 0 || x >= self.cols as isize || y >= self.rows as isize {
+            return None;
         }
-        None
+        Some(self.data[y as usize * self.cols + x as usize])
     }
 }
 
@@ -161,28 +102,7 @@ impl FromStr for Map {
         }
 
         let height = input.lines().count();
-        let width = input.find('\r').ok_or(())?;
+        let width = input.lines().next().ok_or(())?.len();
         Ok(Map::new(data, width, height))
     }
 }
-
-#[derive(Hash, Eq, PartialEq, Clone)]
-struct Point {
-    x: i16,
-    y: i16,
-}
-
-impl Point {
-    fn new(x: i16, y: i16) -> Point {
-        Point { x, y }
-    }
-
-    fn get_neighbours(&self) -> [Point; 4] {
-        [
-            Point::new(self.x - 1, self.y - 0),
-            Point::new(self.x + 1, self.y - 0),
-            Point::new(self.x + 0, self.y - 1),
-            Point::new(self.x + 0, self.y + 1),
-        ]
-    }
-}
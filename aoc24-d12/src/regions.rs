@@ -0,0 +1,351 @@
+use crate::Map;
+use colored::Colorize;
+use std::collections::VecDeque;
+
+const NO_REGION: usize = usize::MAX;
+
+/// The smallest rectangle around a region, all coordinates are inclusive.
+pub struct BoundingBox {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+impl BoundingBox {
+    fn size(&self) -> usize {
+        (self.right - self.left + 1) * (self.bottom - self.top + 1)
+    }
+}
+
+pub struct Region {
+    pub id: usize,
+    pub plant: char,
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+    pub bounding_box: BoundingBox,
+    // the number of separate groups of other regions that are completely surrounded by this region
+    pub holes: usize,
+    // the innermost region that completely surrounds this region, if any
+    pub enclosing_region: Option<usize>,
+}
+
+impl Region {
+    pub fn price(&self) -> usize {
+        self.area * self.perimeter
+    }
+
+    pub fn discounted_price(&self) -> usize {
+        self.area * self.sides
+    }
+}
+
+/// What is counted for a region while labelling.
+#[derive(Default)]
+pub struct RegionSize {
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+}
+
+/// The region of every plot (in the same order as the map), and the size of every region.
+/// Regions are numbered in the order their top left plot appears on the map (row by row).
+pub struct Labels {
+    pub labels: Vec<usize>,
+    pub sizes: Vec<RegionSize>,
+}
+
+/// Labels the regions in a single scan over the map (row by row), using union-find:
+/// a plot gets the label of the plot to the left or above when it has the same plant,
+/// when both have the same plant but a different label, those labels are the same region and get merged.
+/// Everything is counted per plot while scanning, and added to the region when labels get merged.
+pub fn label_regions(map: &Map) -> Labels {
+    let mut labels = vec![0; map.data.len()];
+    let mut parents: Vec<usize> = Vec::new();
+    let mut sizes: Vec<RegionSize> = Vec::new();
+
+    for y in 0..map.rows {
+        for x in 0..map.cols {
+            let index = y * map.cols + x;
+            let plant = map.data[index];
+            let same =
+                |dx: isize, dy: isize| map.get(x as isize + dx, y as isize + dy) == Some(plant);
+
+            let left = same(-1, 0).then(|| find(&mut parents, labels[index - 1]));
+            let up = same(0, -1).then(|| find(&mut parents, labels[index - map.cols]));
+            let label = match (left, up) {
+                (Some(left), Some(up)) if left != up => {
+                    parents[up] = left;
+                    let merged = std::mem::take(&mut sizes[up]);
+                    sizes[left].area += merged.area;
+                    sizes[left].perimeter += merged.perimeter;
+                    sizes[left].sides += merged.sides;
+                    left
+                }
+                (Some(label), _) | (None, Some(label)) => label,
+                (None, None) => {
+                    parents.push(parents.len());
+                    sizes.push(RegionSize::default());
+                    parents.len() - 1
+                }
+            };
+            labels[index] = label;
+
+            let size = &mut sizes[label];
+            size.area += 1;
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if !same(dx, dy) {
+                    size.perimeter += 1;
+                }
+            }
+            // a region has as many sides as corners: an outer corner has no neighbours on both sides,
+            // an inner corner has both but not the diagonal (with both sides the same plant, the diagonal is in the same region)
+            for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let (horizontal, vertical) = (same(dx, 0), same(0, dy));
+                if (!horizontal && !vertical) || (horizontal && vertical && !same(dx, dy)) {
+                    size.sides += 1;
+                }
+            }
+        }
+    }
+
+    // only the labels that weren't merged into another one are complete regions, number those in order
+    let mut ids = vec![NO_REGION; parents.len()];
+    let mut regions = Vec::new();
+    for label in labels.iter_mut() {
+        let root = find(&mut parents, *label);
+        if ids[root] == NO_REGION {
+            ids[root] = regions.len();
+            regions.push(std::mem::take(&mut sizes[root]));
+        }
+        *label = ids[root];
+    }
+    Labels {
+        labels,
+        sizes: regions,
+    }
+}
+
+fn find(parents: &mut [usize], mut label: usize) -> usize {
+    while parents[label] != label {
+        // path halving: skip a level on the way up, to keep the trees flat
+        parents[label] = parents[parents[label]];
+        label = parents[label];
+    }
+    label
+}
+
+/// All regions of a garden map, with the region of every plot.
+pub struct Garden {
+    plants: Vec<char>,
+    // the region id of every plot, in the same order as the map
+    labels: Vec<usize>,
+    regions: Vec<Region>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Garden {
+    pub fn new(map: &Map) -> Garden {
+        let Labels { labels, sizes } = label_regions(map);
+        let mut sizes = sizes.into_iter();
+        let mut regions: Vec<Region> = Vec::new();
+        for (index, &id) in labels.iter().enumerate() {
+            let (x, y) = (index % map.cols, index / map.cols);
+            if id == regions.len() {
+                // the first plot of a region on the map
+                let size = sizes.next().unwrap();
+                regions.push(Region {
+                    id,
+                    plant: map.data[index],
+                    area: size.area,
+                    perimeter: size.perimeter,
+                    sides: size.sides,
+                    bounding_box: BoundingBox {
+                        left: x,
+                        top: y,
+                        right: x,
+                        bottom: y,
+                    },
+                    holes: 0,
+                    enclosing_region: None,
+                });
+            }
+            let bounding_box = &mut regions[id].bounding_box;
+            bounding_box.left = bounding_box.left.min(x);
+            bounding_box.right = bounding_box.right.max(x);
+            bounding_box.bottom = y;
+        }
+        let mut garden = Garden {
+            plants: map.data.clone(),
+            labels,
+            regions,
+            rows: map.rows,
+            cols: map.cols,
+        };
+        garden.find_enclosures();
+        garden
+    }
+
+    /// All regions, in the order their top left plot appears on the map (row by row).
+    pub fn regions(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    pub fn region(&self, id: usize) -> Option<&Region> {
+        self.regions.get(id)
+    }
+
+    fn label(&self, x: isize, y: isize) -> usize {
+        if x < 0 || y < 0 || x >= self.cols as isize || y >= self.rows as isize {
+            return NO_REGION;
+        }
+        self.labels[y as usize * self.cols + x as usize]
+    }
+
+    fn find_enclosures(&mut self) {
+        for id in 0..self.regions.len() {
+            // flood everything that is not this region from just outside the bounding box,
+            // what can't be reached from there is surrounded by the region
+            let bounding_box = &self.regions[id].bounding_box;
+            let left = bounding_box.left as isize - 1;
+            let top = bounding_box.top as isize - 1;
+            let width = bounding_box.right - bounding_box.left + 3;
+            let height = bounding_box.bottom - bounding_box.top + 3;
+            let mut reached = vec![false; width * height];
+            let mut queue = VecDeque::from([0]);
+            reached[0] = true;
+            while let Some(index) = queue.pop_front() {
+                let (x, y) = ((index % width) as isize, (index / width) as isize);
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                        continue;
+                    }
+                    let neighbour = ny as usize * width + nx as usize;
+                    if !reached[neighbour] && self.label(left + nx, top + ny) != id {
+                        reached[neighbour] = true;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            // every group of plots that wasn't reached is a hole
+            for index in 0..reached.len() {
+                let (x, y) = ((index % width) as isize, (index / width) as isize);
+                if reached[index] || self.label(left + x, top + y) == id {
+                    continue;
+                }
+                self.regions[id].holes += 1;
+                let mut queue = VecDeque::from([index]);
+                reached[index] = true;
+                while let Some(index) = queue.pop_front() {
+                    let (x, y) = ((index % width) as isize, (index / width) as isize);
+                    self.set_enclosing_region(self.label(left + x, top + y), id);
+                    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                        // the hole can't reach the frame, so it never leaves the box
+                        let neighbour = (y + dy) as usize * width + (x + dx) as usize;
+                        if !reached[neighbour] && self.label(left + x + dx, top + y + dy) != id {
+                            reached[neighbour] = true;
+                            queue.push_back(neighbour);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_enclosing_region(&mut self, inner: usize, outer: usize) {
+        // when several regions surround a region, they surround each other too, the smallest one is innermost
+        let size = self.regions[outer].bounding_box.size();
+        match self.regions[inner].enclosing_region {
+            Some(current) if self.regions[current].bounding_box.size() <= size => {}
+            _ => self.regions[inner].enclosing_region = Some(outer),
+        }
+    }
+
+    /// Prints the map with a colour per region, and fences between the regions.
+    pub fn print(&self) {
+        let fence = |a: usize, b: usize| a != b;
+        for y in 0..=self.rows as isize {
+            // the line with the fences above the row
+            for x in 0..=self.cols as isize {
+                let around = [
+                    self.label(x - 1, y - 1),
+                    self.label(x, y - 1),
+                    self.label(x - 1, y),
+                    self.label(x, y),
+                ];
+                let corner = fence(around[0], around[1])
+                    || fence(around[2], around[3])
+                    || fence(around[0], around[2])
+                    || fence(around[1], around[3]);
+                print!(
+                    "{}",
+                    if corner { "+" } else { " " }.on_region(self, around[3])
+                );
+                if x < self.cols as isize {
+                    let horizontal = fence(around[1], around[3]);
+                    print!(
+                        "{}",
+                        if horizontal { "---" } else { "   " }.on_region(self, around[3])
+                    );
+                }
+            }
+            println!();
+            if y == self.rows as isize {
+                break;
+            }
+
+            // the row itself
+            for x in 0..=self.cols as isize {
+                let (left, right) = (self.label(x - 1, y), self.label(x, y));
+                print!(
+                    "{}",
+                    if fence(left, right) { "|" } else { " " }.on_region(self, right)
+                );
+                if x < self.cols as isize {
+                    let plant = self.plants[y as usize * self.cols + x as usize];
+                    print!("{}", format!(" {plant} ").as_str().on_region(self, right));
+                }
+            }
+            println!();
+        }
+    }
+
+    fn colour(&self, id: usize) -> (u8, u8, u8) {
+        // spread the colours, so that neighbouring regions (with close ids) look different
+        let hue = (id * 137) % 360;
+        let (r, g, b) = match hue / 60 {
+            0 => (255, hue * 255 / 60, 0),
+            1 => (255 - (hue - 60) * 255 / 60, 255, 0),
+            2 => (0, 255, (hue - 120) * 255 / 60),
+            3 => (0, 255 - (hue - 180) * 255 / 60, 255),
+            4 => ((hue - 240) * 255 / 60, 0, 255),
+            _ => (255, 0, 255 - (hue - 300) * 255 / 60),
+        };
+        // pastel, so the black letters and fences stay readable
+        (
+            (r / 2 + 100) as u8,
+            (g / 2 + 100) as u8,
+            (b / 2 + 100) as u8,
+        )
+    }
+}
+
+trait OnRegion {
+    fn on_region(self, garden: &Garden, id: usize) -> colored::ColoredString;
+}
+
+impl OnRegion for &str {
+    fn on_region(self, garden: &Garden, id: usize) -> colored::ColoredString {
+        match id {
+            NO_REGION => self.normal(),
+            _ => {
+                let (r, g, b) = garden.colour(id);
+                self.black().on_truecolor(r, g, b)
+            }
+        }
+    }
+}
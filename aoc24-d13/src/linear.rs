@@ -0,0 +1,333 @@
+/// A button (or the prize) as a movement along the X and Y axis.
+pub type Vector = (i128, i128);
+
+/// Finds the cheapest number of presses (a, b) so that a * button_a + b * button_b lands exactly on the prize,
+/// optionally pressing each button at most as often as its limit. Returns None when the prize can't be reached.
+pub fn cheapest_presses(
+    button_a: Vector,
+    button_b: Vector,
+    prize: Vector,
+    costs: (i128, i128),
+    limits: (Option<i128>, Option<i128>),
+) -> Option<(i128, i128)> {
+    // Cramer's rule: with two independent buttons there is exactly one (rational) solution
+    let determinant = button_a.0 * button_b.1 - button_a.1 * button_b.0;
+    if determinant != 0 {
+        let numerator_a = prize.0 * button_b.1 - prize.1 * button_b.0;
+        let numerator_b = button_a.0 * prize.1 - button_a.1 * prize.0;
+        if numerator_a % determinant != 0 || numerator_b % determinant != 0 {
+            return None; // only complete presses count
+        }
+        let presses = (numerator_a / determinant, numerator_b / determinant);
+        let in_limit =
+            |p: i128, limit: Option<i128>| p >= 0 && limit.is_none_or(|limit| p <= limit);
+        return (in_limit(presses.0, limits.0) && in_limit(presses.1, limits.1)).then_some(presses);
+    }
+
+    // the buttons move along the same line, so the prize has to be on that line too
+    let on_line = |button: Vector| button.0 * prize.1 - button.1 * prize.0 == 0;
+    if !on_line(button_a) || !on_line(button_b) {
+        return None;
+    }
+    // on a line through the origin, one axis is enough (unless the line is the Y axis)
+    let (a, b, target) = if button_a.0 != 0 || button_b.0 != 0 || prize.0 != 0 {
+        (button_a.0, button_b.0, prize.0)
+    } else {
+        (button_a.1, button_b.1, prize.1)
+    };
+    cheapest_presses_on_line(a, b, target, costs, limits)
+}
+
+/// The same for three buttons, of which the first two don't move along the same line.
+pub fn cheapest_presses_of_three(
+    buttons: [Vector; 3],
+    prize: Vector,
+    costs: [i128; 3],
+    limits: [Option<i128>; 3],
+) -> Option<[i128; 3]> {
+    let [button_a, button_b, button_c] = buttons;
+    let determinant = button_a.0 * button_b.1 - button_a.1 * button_b.0;
+    assert_ne!(
+        determinant, 0,
+        "the first two buttons move along the same line"
+    );
+    // Cramer's rule for A and B, after pressing C a number of times
+    let solve = |presses_c: i128| {
+        let rest = (
+            prize.0 - presses_c * button_c.0,
+            prize.1 - presses_c * button_c.1,
+        );
+        let numerator_a = rest.0 * button_b.1 - rest.1 * button_b.0;
+        let numerator_b = button_a.0 * rest.1 - button_a.1 * rest.0;
+        (numerator_a % determinant == 0 && numerator_b % determinant == 0).then_some([
+            numerator_a / determinant,
+            numerator_b / determinant,
+            presses_c,
+        ])
+    };
+
+    // whether A and B can make up the rest only depends on the presses of C modulo the determinant,
+    // so the presses of C that work repeat with a fixed step, find the first two to know that step
+    let period = determinant.abs();
+    let mut working = (0..2 * period).filter_map(solve);
+    let first = working.next()?;
+    let second = working.next()?;
+    let steps = [
+        second[0] - first[0],
+        second[1] - first[1],
+        second[2] - first[2],
+    ];
+
+    // the presses of all three buttons change by the same amount with every step, just like on a line
+    let mut range = (None, None);
+    for button in 0..3 {
+        if !restrict(&mut range, first[button], steps[button], limits[button]) {
+            return None;
+        }
+    }
+    let cost_per_step = (0..3).map(|button| costs[button] * steps[button]).sum();
+    let step = cheapest_in_range(range, cost_per_step)?;
+    Some([0, 1, 2].map(|button| first[button] + step * steps[button]))
+}
+
+// Solves a * presses_a + b * presses_b = target, for the cheapest non-negative number of presses.
+fn cheapest_presses_on_line(
+    a: i128,
+    b: i128,
+    target: i128,
+    costs: (i128, i128),
+    limits: (Option<i128>, Option<i128>),
+) -> Option<(i128, i128)> {
+    if a == 0 && b == 0 {
+        return (target == 0).then_some((0, 0));
+    }
+    let (gcd, x, y) = extended_gcd(a, b);
+    if target % gcd != 0 {
+        return None;
+    }
+    // all solutions are (a0 + k * step_a, b0 - k * step_b) for any whole k
+    let (a0, b0) = (x * (target / gcd), y * (target / gcd));
+    let (step_a, step_b) = (b / gcd, a / gcd);
+
+    // find the range of k that keeps both press counts between 0 and the limit
+    let mut range = (None, None);
+    if !restrict(&mut range, a0, step_a, limits.0) || !restrict(&mut range, b0, -step_b, limits.1) {
+        return None;
+    }
+    let k = cheapest_in_range(range, costs.0 * step_a - costs.1 * step_b)?;
+    Some((a0 + k * step_a, b0 - k * step_b))
+}
+
+// Every step changes the cost by the same amount, so the cheapest solution is at one of the ends of the range.
+fn cheapest_in_range(range: (Option<i128>, Option<i128>), cost_per_step: i128) -> Option<i128> {
+    match range {
+        (Some(low), Some(high)) if low > high => None,
+        (low, _) if cost_per_step > 0 => low,
+        (low, high) => high.or(low),
+    }
+}
+
+// Narrows the range of k so that 0 <= base + k * step <= limit, returns false when no k fits.
+fn restrict(
+    range: &mut (Option<i128>, Option<i128>),
+    base: i128,
+    step: i128,
+    limit: Option<i128>,
+) -> bool {
+    if step == 0 {
+        return base >= 0 && limit.is_none_or(|limit| base <= limit);
+    }
+    // dividing by a negative step turns the lower bound into an upper bound, and the other way around
+    if step > 0 {
+        raise(&mut range.0, ceil_div(-base, step));
+        if let Some(limit) = limit {
+            lower(&mut range.1, floor_div(limit - base, step));
+        }
+    } else {
+        lower(&mut range.1, floor_div(-base, step));
+        if let Some(limit) = limit {
+            raise(&mut range.0, ceil_div(limit - base, step));
+        }
+    }
+    true
+}
+
+fn raise(bound: &mut Option<i128>, value: i128) {
+    *bound = Some(bound.map_or(value, |current| current.max(value)));
+}
+
+fn lower(bound: &mut Option<i128>, value: i128) {
+    *bound = Some(bound.map_or(value, |current| current.min(value)));
+}
+
+/// Returns (gcd, x, y) with a * x + b * y = gcd, and the gcd never negative.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return if a < 0 { (-a, -1, 0) } else { (a, 1, 0) };
+    }
+    let (gcd, x, y) = extended_gcd(b, a % b);
+    (gcd, y, x - (a / b) * y)
+}
+
+pub fn floor_div(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+fn ceil_div(numerator: i128, denominator: i128) -> i128 {
+    -floor_div(-numerator, denominator)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // The cheapest presses by trying every number of presses, only for small machines.
+    pub(crate) fn brute_force(
+        buttons: &[Vector],
+        prize: Vector,
+        costs: &[i128],
+        limits: &[Option<i128>],
+    ) -> Option<i128> {
+        let most = |button: usize| {
+            let (x, y) = buttons[button];
+            let before_passing = match (x, y) {
+                (0, 0) => 0,
+                (0, y) => prize.1 / y,
+                (x, 0) => prize.0 / x,
+                (x, y) => (prize.0 / x).min(prize.1 / y),
+            };
+            limits[button].map_or(before_passing, |limit| limit.min(before_passing))
+        };
+        let mut best = None;
+        let mut presses = vec![0; buttons.len()];
+        loop {
+            let position = presses
+                .iter()
+                .zip(buttons)
+                .fold((0, 0), |(x, y), (p, b)| (x + p * b.0, y + p * b.1));
+            if position == prize {
+                let cost: i128 = presses.iter().zip(costs).map(|(p, c)| p * c).sum();
+                best = Some(best.map_or(cost, |best: i128| best.min(cost)));
+            }
+            // the next combination of presses, like counting
+            let Some(button) = (0..buttons.len()).find(|&button| presses[button] < most(button))
+            else {
+                return best;
+            };
+            presses[button] += 1;
+            presses[..button].fill(0);
+        }
+    }
+
+    // Checks that the presses land on the prize, stay within the limits, and cost as little as the brute force.
+    pub(crate) fn check(
+        buttons: &[Vector],
+        prize: Vector,
+        costs: &[i128],
+        limits: &[Option<i128>],
+        presses: Option<&[i128]>,
+    ) {
+        let expected = brute_force(buttons, prize, costs, limits);
+        let context = format!("buttons {buttons:?}, prize {prize:?}, limits {limits:?}");
+        let Some(presses) = presses else {
+            assert_eq!(expected, None, "{context}");
+            return;
+        };
+        let position = presses
+            .iter()
+            .zip(buttons)
+            .fold((0, 0), |(x, y), (p, b)| (x + p * b.0, y + p * b.1));
+        assert_eq!(position, prize, "{context}: {presses:?}");
+        for (p, limit) in presses.iter().zip(limits) {
+            assert!(
+                *p >= 0 && limit.is_none_or(|limit| *p <= limit),
+                "{context}: {presses:?}"
+            );
+        }
+        let cost = presses.iter().zip(costs).map(|(p, c)| p * c).sum();
+        assert_eq!(Some(cost), expected, "{context}: {presses:?}");
+    }
+
+    // every movement with both axes between 0 and `max`, including standing still
+    pub(crate) fn movements(max: i128) -> Vec<Vector> {
+        (0..=max)
+            .flat_map(|x| (0..=max).map(move |y| (x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn example_machines() {
+        assert_eq!(
+            cheapest_presses((94, 34), (22, 67), (8400, 5400), (3, 1), (None, None)),
+            Some((80, 40))
+        );
+        assert_eq!(
+            cheapest_presses((26, 66), (67, 21), (12748, 12176), (3, 1), (None, None)),
+            None
+        );
+    }
+
+    #[test]
+    fn two_buttons_like_brute_force() {
+        // independent and collinear buttons, buttons that don't move, prizes that can't be won, and limits
+        let limits = [
+            (None, None),
+            (Some(2), None),
+            (None, Some(1)),
+            (Some(3), Some(3)),
+        ];
+        let costs = [(3, 1), (1, 1), (2, 5)];
+        for a in movements(3) {
+            for b in movements(3) {
+                for prize in movements(9) {
+                    let costs = costs[(prize.0 + prize.1) as usize % costs.len()];
+                    for limits in limits {
+                        let presses = cheapest_presses(a, b, prize, costs, limits);
+                        check(
+                            &[a, b],
+                            prize,
+                            &[costs.0, costs.1],
+                            &[limits.0, limits.1],
+                            presses.map(|(a, b)| [a, b]).as_ref().map(|p| &p[..]),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn three_buttons_like_brute_force() {
+        let limits = [
+            [None, None, None],
+            [Some(2), None, Some(1)],
+            [None, Some(3), None],
+        ];
+        let costs = [[3, 1, 2], [1, 4, 1], [2, 2, 7]];
+        for a in movements(2) {
+            for b in movements(2).into_iter().filter(|&b| a.0 * b.1 != a.1 * b.0) {
+                for c in movements(2) {
+                    for prize in movements(7) {
+                        let costs = costs[(prize.0 + 2 * prize.1) as usize % costs.len()];
+                        for limits in limits {
+                            let presses =
+                                cheapest_presses_of_three([a, b, c], prize, costs, limits);
+                            check(
+                                &[a, b, c],
+                                prize,
+                                &costs,
+                                &limits,
+                                presses.as_ref().map(|p| &p[..]),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,66 +1,105 @@
 ﻿use aoc24_tools::*;
-use std::f64::consts::PI;
 use std::fs::read_to_string;
 
+mod linear;
+mod optimiser;
+
 const DAY: u8 = 13;
 const TEN_TRILLION: u64 = 10_000_000_000_000;
+// tokens it costs to press button A and button B
+const BUTTON_COSTS: [u64; 2] = [3, 1];
+// in part 1, no button is pressed more than 100 times
+const PRESS_LIMIT: u64 = 100;
 
 fn main() {
     init_measurements!();
     print_header(DAY, "Claw Contraption");
 
     let data = read_to_string("input.txt").unwrap();
-    let (part1, part2) = measure_total!({ solve(&data) });
+    // optional: cargo run -r -- --costs 3,1,2 [--limits -,-,10], a cost (and limit) for each button, - is no limit
+    let costs: Vec<u64> = get_argument("costs").map_or(BUTTON_COSTS.to_vec(), |costs| {
+        costs.split(',').map(|cost| cost.parse().unwrap()).collect()
+    });
+    let limits: Vec<Option<u64>> = get_argument("limits").map_or(Vec::new(), |limits| {
+        limits.split(',').map(|limit| limit.parse().ok()).collect()
+    });
+    let (part1, part2) = match measure_total!({ solve(&data, &costs, &limits) }) {
+        Ok(parts) => parts,
+        Err(error) => {
+            println!("{error}");
+            return;
+        }
+    };
 
     println!("Tokens to win all (Part 1): {part1}");
     println!("Tokens to win all + 10,000,000,000,000 (Part 2): {part2}");
 
     print_summary(DAY);
+
+    // optional: cargo run -r -- --presses
+    if has_flag("presses") {
+        print_presses(&data, &costs, &limits);
+    }
+}
+
+fn print_presses(input: &str, costs: &[u64], limits: &[Option<u64>]) {
+    let machines = ClawMachine::from_string(input, costs, limits).unwrap();
+    for (part, prize_offset, press_limit) in [(1, 0, Some(PRESS_LIMIT)), (2, TEN_TRILLION, None)] {
+        println!("Part {part}:");
+        for (number, machine) in machines.iter().enumerate() {
+            match machine.cheapest_presses(prize_offset, press_limit) {
+                Some(presses) => {
+                    let buttons: Vec<_> = presses
+                        .presses
+                        .iter()
+                        .enumerate()
+                        .map(|(button, count)| format!("{}x{count}", (b'A' + button as u8) as char))
+                        .collect();
+                    println!(
+                        "Machine {:3}: {} = {} tokens",
+                        number + 1,
+                        buttons.join(" "),
+                        presses.tokens
+                    );
+                }
+                None => println!("Machine {:3}: can't be won", number + 1),
+            }
+        }
+    }
 }
 
-fn solve(input: &str) -> (u64, u64) {
-    let mut machines = measure!({ ClawMachine::from_string(input) }, "1-parsing");
-    let part1 = measure!({ calculate_tokens_using_iteration(&machines) }, "1-iterate");
-    measure!(
-        { ClawMachine::add_to_prices(&mut machines, TEN_TRILLION) },
-        "2-clone"
+fn solve(input: &str, costs: &[u64], limits: &[Option<u64>]) -> Result<(u64, u64), String> {
+    let machines = measure!(
+        { ClawMachine::from_string(input, costs, limits) },
+        "1-parsing"
+    )?;
+    let part1 = measure!(
+        { calculate_tokens(&machines, 0, Some(PRESS_LIMIT)) },
+        "1-solve"
     );
     let part2 = measure!(
-        { calculate_tokens_using_trigonometry(&machines) },
-        "2-trigono."
+        { calculate_tokens(&machines, TEN_TRILLION, None) },
+        "2-solve"
     );
-    (part1, part2)
+    Ok((part1, part2))
 }
 
-fn calculate_tokens_using_trigonometry(machines: &Vec<ClawMachine>) -> u64 {
-    let mut result = 0;
-    for machine in machines {
-        result += machine
-            .calculate_tokens_to_win_using_trigonometry()
-            .unwrap_or(0)
-    }
-    result
-}
-
-fn calculate_tokens_using_iteration(machines: &Vec<ClawMachine>) -> u64 {
-    let mut result = 0;
-    for machine in machines {
-        result += machine
-            .calculate_tokens_to_win_using_iteration()
-            .unwrap_or(0)
-    }
-    result
+fn calculate_tokens(machines: &[ClawMachine], prize_offset: u64, press_limit: Option<u64>) -> u64 {
+    machines
+        .iter()
+        .filter_map(|machine| machine.calculate_tokens_to_win(prize_offset, press_limit))
+        .sum()
 }
 
 struct ClawMachine {
-    button_a: Button,
-    button_b: Button,
+    buttons: Vec<Button>,
     prize: Point,
 }
 
 struct Button {
     movement: Point,
     tokens: u64,
+    limit: Option<u64>,
 }
 
 struct Point {
@@ -69,111 +108,42 @@ struct Point {
 }
 
 impl ClawMachine {
-    fn calculate_tokens_to_win_using_iteration(self: &Self) -> Option<u64> {
-        // just loop over all options until we find one
-        // the good thing is that there is always only one solution to the problem
-        for press_a in 0..100 {
-            for press_b in 0..100 {
-                let result = self.gamble_on(&press_a, &press_b);
-                if result.is_some() {
-                    return result;
-                }
-            }
-        }
-        None
+    fn calculate_tokens_to_win(&self, prize_offset: u64, press_limit: Option<u64>) -> Option<u64> {
+        Some(self.cheapest_presses(prize_offset, press_limit)?.tokens)
     }
 
-    fn gamble_on(&self, press_a: &u64, press_b: &u64) -> Option<u64> {
-        let result_x = press_a * self.button_a.movement.x + press_b * self.button_b.movement.x;
-        if result_x == self.prize.x {
-            let result_y = press_a * self.button_a.movement.y + press_b * self.button_b.movement.y;
-            if result_y == self.prize.y {
-                return Some(press_a * self.button_a.tokens + press_b * self.button_b.tokens);
-            }
-        }
-        None
-    }
-
-    fn calculate_tokens_to_win_using_trigonometry(self: &Self) -> Option<u64> {
-        // calculate the angle's of the vectors based on the x-axis (1,0)
-        let angle_prize = self.prize.angle();
-        let angle_a = self.button_a.movement.angle();
-        let angle_b = self.button_b.movement.angle();
-
-        // check if the vector to the prize is between the movement of buttons A & B, if not,
-        // the machine is rigged to never win.
-        if (angle_prize > angle_a && angle_prize > angle_b)
-            || (angle_prize < angle_a && angle_prize < angle_b)
-        {
-            return None;
-        }
-
-        // the vector's should form a triangle, so let's calculate the angles of each of the corners of the triangle
-        let corner_prize_a = (angle_prize - angle_a).abs();
-        let corner_prize_b = (angle_prize - angle_b).abs();
-        let corner_a_b = PI - corner_prize_a - corner_prize_b;
-
-        // apply the law of sines to find the required distances to move in the directions of A & B to arrive complete the triangle
-        let distance_to_prize = self.prize.distance();
-        let radius_x2 = distance_to_prize / corner_a_b.sin();
-        let distance_for_a = radius_x2 * corner_prize_b.sin();
-        let distance_for_b = radius_x2 * corner_prize_a.sin();
-
-        // divide to find the number of button presses needed
-        let presses_a = distance_for_a / self.button_a.movement.distance();
-        let presses_b = distance_for_b / self.button_b.movement.distance();
-
-        // only complete presses count, there might be some rounding issues, so ignore those
-        let presses_a_rounded = presses_a.round();
-        let presses_b_rounded = presses_b.round();
-        if (presses_a_rounded - presses_a).abs() > 0.001
-            || (presses_b_rounded - presses_b).abs() > 0.001
-        {
-            return None;
-        }
-
-        // to be really sure, we could use the gamble-method from part 1 to check, but I got the right answer without :)
-        Some(
-            presses_a_rounded as u64 * self.button_a.tokens
-                + presses_b_rounded as u64 * self.button_b.tokens,
-        )
-    }
-
-    fn from_string(input: &str) -> Vec<ClawMachine> {
+    /// Every button needs a cost, returns an error when a machine has more buttons than there are costs.
+    fn from_string(
+        input: &str,
+        costs: &[u64],
+        limits: &[Option<u64>],
+    ) -> Result<Vec<ClawMachine>, String> {
         let mut result: Vec<ClawMachine> = Vec::with_capacity(500);
 
-        let mut button_a = "";
-        let mut button_b = "";
-        for (index, line) in input.lines().enumerate() {
-            match index % 4 {
-                0 => button_a = line,
-                1 => button_b = line,
-                2 => {
-                    let button_a = Button::from_string(button_a, 3);
-                    let button_b = Button::from_string(button_b, 1);
-                    let prize = Point::from_prize_string(line);
-                    result.push(ClawMachine {
-                        button_a,
-                        button_b,
-                        prize,
-                    })
-                }
-                _ => {}
+        // any number of buttons, up to the prize
+        let mut buttons = Vec::new();
+        for line in input.lines() {
+            if line.starts_with("Button") {
+                let index = buttons.len();
+                let tokens = *costs.get(index).ok_or_else(|| {
+                    format!(
+                        "Machine {} has more buttons than the {} cost(s) given with --costs",
+                        result.len() + 1,
+                        costs.len()
+                    )
+                })?;
+                let limit = limits.get(index).copied().flatten();
+                buttons.push(Button::from_string(line, tokens, limit));
+            } else if line.starts_with("Prize") {
+                let prize = Point::from_prize_string(line);
+                result.push(ClawMachine {
+                    buttons: std::mem::take(&mut buttons),
+                    prize,
+                })
             }
         }
 
-        result
-    }
-
-    fn add_to_prices(machines: &mut Vec<ClawMachine>, prize_increment: u64) {
-        for machine in machines {
-            machine.add_to_prize(prize_increment);
-        }
-    }
-
-    fn add_to_prize(&mut self, prize_increment: u64) {
-        self.prize.x += prize_increment;
-        self.prize.y += prize_increment;
+        Ok(result)
     }
 }
 
@@ -189,26 +159,20 @@ impl Point {
         Point::new(x, y)
     }
 
-    fn angle(&self) -> f64 {
-        (self.y as f64 / self.x as f64).atan()
-    }
-
-    fn distance(&self) -> f64 {
-        // apply Pythagorean theorem
-        let x = self.x as u128;
-        let y = self.y as u128;
-        ((x * x + y * y) as f64).sqrt()
+    fn to_vector(&self) -> linear::Vector {
+        (self.x as i128, self.y as i128)
     }
 }
 
 impl Button {
-    fn from_string(line: &str, tokens: u64) -> Button {
+    fn from_string(line: &str, tokens: u64, limit: Option<u64>) -> Button {
         let split_index = line.find(',').unwrap();
         let x = line[12..split_index].parse().unwrap();
         let y = line[split_index + 4..].parse().unwrap();
         Button {
             movement: Point { x, y },
             tokens,
+            limit,
         }
     }
 }
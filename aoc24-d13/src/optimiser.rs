@@ -0,0 +1,444 @@
+use crate::linear::{self, Vector};
+use crate::ClawMachine;
+
+/// How often each button is pressed (in the order of the machine's buttons) and the tokens it costs.
+pub struct Presses {
+    pub presses: Vec<u64>,
+    pub tokens: u64,
+}
+
+impl ClawMachine {
+    /// Finds the cheapest way to win the prize with any number of buttons, or None when it can't be won.
+    /// Three buttons are solved exactly, the presses of any other buttons are searched with branch and bound.
+    pub fn cheapest_presses(&self, prize_offset: u64, press_limit: Option<u64>) -> Option<Presses> {
+        let prize = (
+            self.prize.x as i128 + prize_offset as i128,
+            self.prize.y as i128 + prize_offset as i128,
+        );
+        let buttons: Vec<_> = self
+            .buttons
+            .iter()
+            .map(|button| {
+                let limit = match (button.limit, press_limit) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (limit, None) | (None, limit) => limit,
+                };
+                SearchButton {
+                    movement: button.movement.to_vector(),
+                    cost: button.tokens as i128,
+                    limit: limit.map(|limit| limit as i128),
+                }
+            })
+            .collect();
+
+        // buttons only move forward, so every button can only be pressed so often before it passes the prize
+        let most_presses = |button: &SearchButton| {
+            let before_passing = [(button.movement.0, prize.0), (button.movement.1, prize.1)]
+                .into_iter()
+                .filter(|&(movement, _)| movement > 0)
+                .map(|(movement, prize)| prize / movement)
+                .min()
+                .unwrap_or(0); // a button that doesn't move is never worth pressing
+            button
+                .limit
+                .map_or(before_passing, |limit| limit.min(before_passing))
+        };
+
+        // solve the buttons that can be pressed most often exactly, and search through the presses of the others
+        let mut order: Vec<_> = (0..buttons.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(most_presses(&buttons[i])));
+        let exact = match order.len() {
+            0..=2 => order.len(),
+            _ if independent(buttons[order[0]].movement, buttons[order[1]].movement) => 3,
+            _ if independent(buttons[order[0]].movement, buttons[order[2]].movement) => {
+                order.swap(1, 2);
+                3
+            }
+            _ => 2, // the three of them are on a line, so the third doesn't add anything new
+        };
+
+        let mut search = Search {
+            buttons: &buttons,
+            exact: &order[..exact],
+            searched: &order[exact..],
+            presses: vec![0; buttons.len()],
+            best: None,
+        };
+        search.search(0, prize, 0);
+        search.best.map(|(presses, tokens)| Presses {
+            presses: presses.into_iter().map(|p| p as u64).collect(),
+            tokens: tokens as u64,
+        })
+    }
+}
+
+struct SearchButton {
+    movement: Vector,
+    cost: i128,
+    limit: Option<i128>,
+}
+
+struct Search<'a> {
+    buttons: &'a [SearchButton],
+    exact: &'a [usize],
+    searched: &'a [usize],
+    presses: Vec<i128>,
+    best: Option<(Vec<i128>, i128)>,
+}
+
+impl Search<'_> {
+    // Branch and bound: try the presses of one button at a time, and skip everything that can't beat the best so far.
+    fn search(&mut self, depth: usize, rest: Vector, cost: i128) {
+        let Some(&index) = self.searched.get(depth) else {
+            if let Some((presses, exact_cost)) = self.solve_exact(rest) {
+                let mut result = self.presses.clone();
+                for (&i, p) in self.exact.iter().zip(presses) {
+                    result[i] = p;
+                }
+                if self
+                    .best
+                    .as_ref()
+                    .is_none_or(|(_, best)| cost + exact_cost < *best)
+                {
+                    self.best = Some((result, cost + exact_cost));
+                }
+            }
+            return;
+        };
+        let remaining: Vec<_> = self.searched[depth..]
+            .iter()
+            .chain(self.exact)
+            .copied()
+            .collect();
+        if !self.in_lattice(&remaining, rest) {
+            return; // no whole number of presses adds up to the rest, however many presses are allowed
+        }
+
+        // Start at the presses of the relaxed (fractional) optimum, and go up and down from there.
+        // The cheapest total for a number of presses only goes up when moving away from that optimum,
+        // so each direction stops as soon as it can't beat the best so far, or can't reach the prize at all.
+        let Some((_, relaxed)) = self.relaxed_optimum(&remaining, rest) else {
+            return;
+        };
+        let button = &self.buttons[index];
+        let most = [(button.movement.0, rest.0), (button.movement.1, rest.1)]
+            .into_iter()
+            .filter(|&(movement, _)| movement > 0)
+            .map(|(movement, rest)| rest / movement)
+            .min()
+            .unwrap_or(0);
+        let most = button.limit.map_or(most, |limit| limit.min(most));
+        let start = relaxed
+            .iter()
+            .find(|&&(i, _)| i == index)
+            .map_or(0, |(_, presses)| presses.floor())
+            .min(most);
+        let deeper = &remaining[1..];
+        for presses in start + 1..=most {
+            if !self.try_presses(depth, deeper, presses, rest, cost) {
+                break;
+            }
+        }
+        for presses in (0..=start).rev() {
+            if !self.try_presses(depth, deeper, presses, rest, cost) {
+                break;
+            }
+        }
+        self.presses[index] = 0;
+    }
+
+    // Searches on with a number of presses for the button at this depth, unless that can't beat the best so far.
+    fn try_presses(
+        &mut self,
+        depth: usize,
+        deeper: &[usize],
+        presses: i128,
+        rest: Vector,
+        cost: i128,
+    ) -> bool {
+        let index = self.searched[depth];
+        let button = &self.buttons[index];
+        let rest = (
+            rest.0 - presses * button.movement.0,
+            rest.1 - presses * button.movement.1,
+        );
+        let cost = cost + presses * button.cost;
+        let Some((bound, _)) = self.relaxed_optimum(deeper, rest) else {
+            return false;
+        };
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|&(_, best)| !bound.less_than(best - cost))
+        {
+            return false;
+        }
+        self.presses[index] = presses;
+        self.search(depth + 1, rest, cost);
+        true
+    }
+
+    // The cheapest cost when buttons could be pressed a fraction of a time (ignoring the limits), a linear program.
+    // With two equations, an optimal solution presses at most two buttons, so just try every (pair of) button(s).
+    // Returns the cost and the presses of the buttons used, None when not even fractions reach the rest.
+    fn relaxed_optimum(
+        &self,
+        indexes: &[usize],
+        rest: Vector,
+    ) -> Option<(Fraction, Vec<(usize, Fraction)>)> {
+        if rest == (0, 0) {
+            return Some((Fraction::new(0, 1), Vec::new()));
+        }
+        let mut best: Option<(Fraction, Vec<(usize, Fraction)>)> = None;
+        let mut consider = |cost: Fraction, presses: Vec<(usize, Fraction)>| {
+            if best
+                .as_ref()
+                .is_none_or(|(best, _)| cost.less_than_fraction(best))
+            {
+                best = Some((cost, presses));
+            }
+        };
+        for (n, &i) in indexes.iter().enumerate() {
+            let a = &self.buttons[i];
+            // only this button, when the rest is in its direction
+            if !independent(a.movement, rest) && a.movement != (0, 0) {
+                let (movement, target) = if a.movement.0 != 0 {
+                    (a.movement.0, rest.0)
+                } else {
+                    (a.movement.1, rest.1)
+                };
+                let presses = Fraction::new(target, movement);
+                if presses.numerator >= 0 {
+                    consider(
+                        Fraction::new(a.cost * target, movement),
+                        Vec::from([(i, presses)]),
+                    );
+                }
+            }
+            for &j in indexes[n + 1..].iter() {
+                let b = &self.buttons[j];
+                // Cramer's rule again, both need to be pressed a positive number of times
+                let determinant = a.movement.0 * b.movement.1 - a.movement.1 * b.movement.0;
+                if determinant == 0 {
+                    continue;
+                }
+                let presses_a =
+                    Fraction::new(rest.0 * b.movement.1 - rest.1 * b.movement.0, determinant);
+                let presses_b =
+                    Fraction::new(a.movement.0 * rest.1 - a.movement.1 * rest.0, determinant);
+                if presses_a.numerator >= 0 && presses_b.numerator >= 0 {
+                    let cost = Fraction::new(
+                        a.cost * presses_a.numerator + b.cost * presses_b.numerator,
+                        presses_a.denominator,
+                    );
+                    consider(cost, Vec::from([(i, presses_a), (j, presses_b)]));
+                }
+            }
+        }
+        best
+    }
+
+    // Whether whole (possibly negative) numbers of presses of these buttons add up to the rest.
+    fn in_lattice(&self, indexes: &[usize], rest: Vector) -> bool {
+        // combine the buttons like the Euclidean algorithm, into one vector with the gcd of all X movements,
+        // and the gcd of the Y movements of everything that doesn't move along X anymore
+        let mut pivot = (0, 0);
+        let mut vertical = 0;
+        for &i in indexes {
+            let mut movement = self.buttons[i].movement;
+            while movement.0 != 0 {
+                let quotient = pivot.0 / movement.0;
+                pivot = (
+                    pivot.0 - quotient * movement.0,
+                    pivot.1 - quotient * movement.1,
+                );
+                std::mem::swap(&mut pivot, &mut movement);
+            }
+            vertical = gcd(vertical, movement.1);
+        }
+        let rest_y = if pivot.0 == 0 {
+            if rest.0 != 0 {
+                return false;
+            }
+            rest.1
+        } else {
+            if rest.0 % pivot.0 != 0 {
+                return false;
+            }
+            rest.1 - (rest.0 / pivot.0) * pivot.1
+        };
+        if vertical == 0 {
+            rest_y == 0
+        } else {
+            rest_y % vertical == 0
+        }
+    }
+
+    fn solve_exact(&self, rest: Vector) -> Option<(Vec<i128>, i128)> {
+        let buttons: Vec<_> = self.exact.iter().map(|&i| &self.buttons[i]).collect();
+        let presses = match buttons[..] {
+            [] => (rest == (0, 0)).then(Vec::new)?,
+            [a] => {
+                // pressing a single button is like two buttons, of which the second is never pressed
+                let (presses, _) = linear::cheapest_presses(
+                    a.movement,
+                    (0, 0),
+                    rest,
+                    (a.cost, 0),
+                    (a.limit, Some(0)),
+                )?;
+                Vec::from([presses])
+            }
+            [a, b] => {
+                let (presses_a, presses_b) = linear::cheapest_presses(
+                    a.movement,
+                    b.movement,
+                    rest,
+                    (a.cost, b.cost),
+                    (a.limit, b.limit),
+                )?;
+                Vec::from([presses_a, presses_b])
+            }
+            [a, b, c] => Vec::from(linear::cheapest_presses_of_three(
+                [a.movement, b.movement, c.movement],
+                rest,
+                [a.cost, b.cost, c.cost],
+                [a.limit, b.limit, c.limit],
+            )?),
+            _ => unreachable!("at most three buttons are solved exactly"),
+        };
+        let cost = buttons
+            .iter()
+            .zip(presses.iter())
+            .map(|(b, p)| b.cost * p)
+            .sum();
+        Some((presses, cost))
+    }
+}
+
+fn independent(a: Vector, b: Vector) -> bool {
+    a.0 * b.1 - a.1 * b.0 != 0
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Fraction {
+    numerator: i128,
+    // always positive
+    denominator: i128,
+}
+
+impl Fraction {
+    fn new(numerator: i128, denominator: i128) -> Fraction {
+        if denominator < 0 {
+            Fraction {
+                numerator: -numerator,
+                denominator: -denominator,
+            }
+        } else {
+            Fraction {
+                numerator,
+                denominator,
+            }
+        }
+    }
+
+    fn floor(&self) -> i128 {
+        linear::floor_div(self.numerator, self.denominator)
+    }
+
+    fn less_than(&self, value: i128) -> bool {
+        self.numerator < value * self.denominator
+    }
+
+    fn less_than_fraction(&self, other: &Fraction) -> bool {
+        self.numerator * other.denominator < other.numerator * self.denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::linear::tests::{brute_force, check, movements};
+    use crate::ClawMachine;
+
+    fn machine(buttons: &[(i128, i128)], prize: (i128, i128)) -> String {
+        let mut text = String::new();
+        for (i, (x, y)) in buttons.iter().enumerate() {
+            text += &format!("Button {}: X+{x}, Y+{y}\n", (b'A' + i as u8) as char);
+        }
+        text + &format!("Prize: X={}, Y={}\n\n", prize.0, prize.1)
+    }
+
+    #[test]
+    fn example_machines() {
+        let machines =
+            ClawMachine::from_string(include_str!("../example.txt"), &[3, 1], &[]).unwrap();
+        let tokens: Vec<_> = machines
+            .iter()
+            .map(|machine| machine.calculate_tokens_to_win(0, Some(100)))
+            .collect();
+        assert_eq!(tokens, [Some(280), None, Some(200), None]);
+    }
+
+    #[test]
+    fn more_buttons_than_costs() {
+        let input = machine(&[(1, 2), (2, 1), (1, 1)], (5, 5));
+        assert!(ClawMachine::from_string(&input, &[3, 1], &[]).is_err());
+    }
+
+    #[test]
+    fn any_number_of_buttons_like_brute_force() {
+        // a simple pseudo-random generator, so the machines are the same on every run
+        let mut seed = 13_u64;
+        let mut random = |below: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % below
+        };
+        let options = movements(3);
+        for _ in 0..3000 {
+            // collinear buttons and buttons that don't move come up too, and so do prizes that can't be won
+            let number_of_buttons = 3 + random(2);
+            let buttons: Vec<_> = (0..number_of_buttons)
+                .map(|_| options[random(options.len())])
+                .collect();
+            let prize = (random(13) as i128, random(13) as i128);
+            let costs: Vec<u64> = (0..number_of_buttons)
+                .map(|_| 1 + random(5) as u64)
+                .collect();
+            let limits: Vec<Option<u64>> = (0..number_of_buttons)
+                .map(|_| [None, Some(1), Some(3)][random(3)])
+                .collect();
+            let press_limit = [None, Some(4)][random(2)];
+
+            let input = machine(&buttons, prize);
+            let machines = ClawMachine::from_string(&input, &costs, &limits).unwrap();
+            let presses = machines[0].cheapest_presses(0, press_limit);
+
+            let costs: Vec<i128> = costs.iter().map(|&cost| cost as i128).collect();
+            let limits: Vec<Option<i128>> = limits
+                .iter()
+                .map(|&limit| match (limit, press_limit) {
+                    (Some(a), Some(b)) => Some(a.min(b) as i128),
+                    (limit, None) | (None, limit) => limit.map(|limit| limit as i128),
+                })
+                .collect();
+            let found: Option<Vec<i128>> = presses
+                .as_ref()
+                .map(|presses| presses.presses.iter().map(|&p| p as i128).collect());
+            check(&buttons, prize, &costs, &limits, found.as_deref());
+            if let Some(presses) = presses {
+                let expected = brute_force(&buttons, prize, &costs, &limits);
+                assert_eq!(Some(presses.tokens as i128), expected, "{input}");
+            }
+        }
+    }
+}
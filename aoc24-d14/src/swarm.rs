@@ -0,0 +1,389 @@
+use std::fmt::Write;
+
+#[derive(Clone, Copy)]
+pub struct Space {
+    pub width: i64,
+    pub height: i64,
+}
+
+impl Space {
+    /// After this many seconds every robot is back where it started.
+    pub fn period(&self) -> i64 {
+        self.width / gcd(self.width, self.height) * self.height
+    }
+}
+
+struct Robot {
+    position: (i64, i64),
+    velocity: (i64, i64),
+}
+
+pub struct Swarm {
+    space: Space,
+    robots: Vec<Robot>,
+}
+
+impl Swarm {
+    pub fn parse(input: &str, space: Space) -> Swarm {
+        let robots = input
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (position, velocity) = line.split_once(' ').unwrap();
+                let pair = |text: &str| {
+                    let (x, y) = text[2..].split_once(',').unwrap();
+                    (x.parse().unwrap(), y.parse().unwrap())
+                };
+                Robot {
+                    position: pair(position),
+                    velocity: pair(velocity),
+                }
+            })
+            .collect();
+        Swarm { space, robots }
+    }
+
+    pub fn space(&self) -> Space {
+        self.space
+    }
+
+    /// Where every robot is after a number of seconds, without simulating the seconds in between.
+    pub fn positions_at(&self, seconds: i64) -> Vec<(i64, i64)> {
+        self.robots
+            .iter()
+            .map(|robot| (self.x_at(robot, seconds), self.y_at(robot, seconds)))
+            .collect()
+    }
+
+    fn x_at(&self, robot: &Robot, seconds: i64) -> i64 {
+        (robot.position.0 + robot.velocity.0 * (seconds % self.space.width))
+            .rem_euclid(self.space.width)
+    }
+
+    fn y_at(&self, robot: &Robot, seconds: i64) -> i64 {
+        (robot.position.1 + robot.velocity.1 * (seconds % self.space.height))
+            .rem_euclid(self.space.height)
+    }
+
+    /// The product of the number of robots in each quadrant. With an odd size the robots exactly in the middle
+    /// don't count, an even size has no middle line and is split into two equal halves.
+    pub fn safety_factor(&self, seconds: i64) -> u64 {
+        let (width, height) = (self.space.width, self.space.height);
+        let mut quadrant_count = [0u64; 4];
+        for (x, y) in self.positions_at(seconds) {
+            if (width % 2 == 1 && x == width / 2) || (height % 2 == 1 && y == height / 2) {
+                continue;
+            }
+            let (right, bottom) = (x >= (width + 1) / 2, y >= (height + 1) / 2);
+            quadrant_count[right as usize * 2 + bottom as usize] += 1;
+        }
+        quadrant_count.iter().product()
+    }
+
+    /// The number of robots on every tile, row by row.
+    pub fn frame(&self, seconds: i64) -> Vec<u32> {
+        let mut tiles = vec![0; (self.space.width * self.space.height) as usize];
+        for (x, y) in self.positions_at(seconds) {
+            tiles[(y * self.space.width + x) as usize] += 1;
+        }
+        tiles
+    }
+
+    /// The frame as a plain PBM image, every tile with at least one robot is black.
+    pub fn to_pbm(&self, seconds: i64) -> String {
+        let mut pbm = format!(
+            "P1\n# after {seconds} seconds\n{} {}\n",
+            self.space.width, self.space.height
+        );
+        for row in self.frame(seconds).chunks(self.space.width as usize) {
+            let line: Vec<_> = row
+                .iter()
+                .map(|&robots| if robots > 0 { "1" } else { "0" })
+                .collect();
+            // PBM lines shouldn't be longer than 70 characters
+            for chunk in line.chunks(35) {
+                writeln!(pbm, "{}", chunk.join(" ")).unwrap();
+            }
+        }
+        pbm
+    }
+}
+
+/// The moment a detector thinks the robots form a picture, and how sure it is about it (from 0 to 1).
+pub struct Detection {
+    pub seconds: i64,
+    pub confidence: f64,
+}
+
+pub trait Detector {
+    fn name(&self) -> &'static str;
+    fn detect(&self, swarm: &Swarm) -> Option<Detection>;
+}
+
+/// A picture pulls the robots together, so the spread (variance) of the X positions is lowest at some second
+/// within the width, and that of the Y positions within the height. The Chinese remainder theorem combines both.
+pub struct VarianceDetector;
+
+impl Detector for VarianceDetector {
+    fn name(&self) -> &'static str {
+        "variance + CRT"
+    }
+
+    fn detect(&self, swarm: &Swarm) -> Option<Detection> {
+        let variances = |length: i64, at: &dyn Fn(&Robot, i64) -> i64| -> Vec<f64> {
+            (0..length)
+                .map(|seconds| {
+                    let (sum, squares) =
+                        swarm.robots.iter().fold((0, 0), |(sum, squares), robot| {
+                            let value = at(robot, seconds);
+                            (sum + value, squares + value * value)
+                        });
+                    let n = swarm.robots.len() as f64;
+                    squares as f64 / n - (sum as f64 / n).powi(2)
+                })
+                .collect()
+        };
+        let x_variances = variances(swarm.space.width, &|robot, seconds| {
+            swarm.x_at(robot, seconds)
+        });
+        let y_variances = variances(swarm.space.height, &|robot, seconds| {
+            swarm.y_at(robot, seconds)
+        });
+        let (x_seconds, x_confidence) = lowest(&x_variances)?;
+        let (y_seconds, y_confidence) = lowest(&y_variances)?;
+
+        let seconds = chinese_remainder(
+            x_seconds as i64,
+            swarm.space.width,
+            y_seconds as i64,
+            swarm.space.height,
+        )?;
+        Some(Detection {
+            seconds,
+            confidence: x_confidence * y_confidence,
+        })
+    }
+}
+
+/// A picture is orderly: the robots are spread over fewer blocks of the space, so their entropy is lowest.
+pub struct EntropyDetector {
+    pub block_size: i64,
+}
+
+impl Detector for EntropyDetector {
+    fn name(&self) -> &'static str {
+        "entropy"
+    }
+
+    fn detect(&self, swarm: &Swarm) -> Option<Detection> {
+        let columns = (swarm.space.width + self.block_size - 1) / self.block_size;
+        let rows = (swarm.space.height + self.block_size - 1) / self.block_size;
+        let n = swarm.robots.len() as f64;
+        let mut blocks = vec![0u32; (columns * rows) as usize];
+        let entropies: Vec<f64> = (0..swarm.space.period())
+            .map(|seconds| {
+                blocks.fill(0);
+                for (x, y) in swarm.positions_at(seconds) {
+                    blocks[((y / self.block_size) * columns + x / self.block_size) as usize] += 1;
+                }
+                blocks
+                    .iter()
+                    .filter(|&&count| count > 0)
+                    .map(|&count| {
+                        let p = count as f64 / n;
+                        -p * p.log2()
+                    })
+                    .sum()
+            })
+            .collect();
+        let (seconds, confidence) = lowest(&entropies)?;
+        Some(Detection {
+            seconds: seconds as i64,
+            confidence,
+        })
+    }
+}
+
+/// A picture is drawn with lines of robots next to each other, so it has the largest group of connected robots.
+pub struct ClusterDetector;
+
+impl Detector for ClusterDetector {
+    fn name(&self) -> &'static str {
+        "largest cluster"
+    }
+
+    fn detect(&self, swarm: &Swarm) -> Option<Detection> {
+        let (width, height) = (swarm.space.width, swarm.space.height);
+        // a tile holds the second it was last occupied, or visited, so nothing has to be cleared between frames
+        let mut occupied = vec![-1; (width * height) as usize];
+        let mut visited = vec![-1; (width * height) as usize];
+        let mut stack = Vec::new();
+        let sizes: Vec<f64> = (0..swarm.space.period())
+            .map(|seconds| {
+                let positions = swarm.positions_at(seconds);
+                for &(x, y) in positions.iter() {
+                    occupied[(y * width + x) as usize] = seconds;
+                }
+                let mut largest = 0;
+                for &(x, y) in positions.iter() {
+                    if visited[(y * width + x) as usize] == seconds {
+                        continue;
+                    }
+                    visited[(y * width + x) as usize] = seconds;
+                    stack.push((x, y));
+                    let mut size = 0;
+                    while let Some((x, y)) = stack.pop() {
+                        size += 1;
+                        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                            if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                                continue;
+                            }
+                            let index = (ny * width + nx) as usize;
+                            if occupied[index] == seconds && visited[index] != seconds {
+                                visited[index] = seconds;
+                                stack.push((nx, ny));
+                            }
+                        }
+                    }
+                    largest = largest.max(size);
+                }
+                // the largest cluster is the best, so turn it around to find the lowest
+                -(largest as f64)
+            })
+            .collect();
+        let (seconds, confidence) = lowest(&sizes)?;
+        Some(Detection {
+            seconds: seconds as i64,
+            confidence,
+        })
+    }
+}
+
+/// The original shortcut: the first second at which no two robots share a tile.
+pub struct NoOverlapDetector;
+
+impl Detector for NoOverlapDetector {
+    fn name(&self) -> &'static str {
+        "no overlap"
+    }
+
+    fn detect(&self, swarm: &Swarm) -> Option<Detection> {
+        let without_overlap: Vec<i64> = (0..swarm.space.period())
+            .filter(|&seconds| swarm.frame(seconds).iter().all(|&robots| robots <= 1))
+            .collect();
+        // the more seconds without overlap, the less this says about a picture
+        Some(Detection {
+            seconds: *without_overlap.first()?,
+            confidence: 1.0 / without_overlap.len() as f64,
+        })
+    }
+}
+
+// Finds the lowest score, with a confidence of how much it stands out from the runner-up,
+// measured in standard deviations of all scores: 1 deviation gives 0.5, 3 deviations 0.75, and so on.
+fn lowest(scores: &[f64]) -> Option<(usize, f64)> {
+    let (best, &lowest) = scores
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(b.1))?;
+    let runner_up = scores
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != best)
+        .map(|(_, &score)| score)
+        .min_by(|a, b| a.total_cmp(b));
+    let Some(runner_up) = runner_up else {
+        return Some((best, 1.0));
+    };
+    let n = scores.len() as f64;
+    let mean = scores.iter().sum::<f64>() / n;
+    let deviation = (scores
+        .iter()
+        .map(|score| (score - mean).powi(2))
+        .sum::<f64>()
+        / n)
+        .sqrt();
+    if deviation == 0.0 {
+        return Some((best, 0.0));
+    }
+    let distance = (runner_up - lowest) / deviation;
+    Some((best, distance / (distance + 1.0)))
+}
+
+// Finds the smallest t >= 0 with t = a (mod m) and t = b (mod n), if there is one.
+fn chinese_remainder(a: i64, m: i64, b: i64, n: i64) -> Option<i64> {
+    let (g, p, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    // t = a + m * k, with m * k = b - a (mod n)
+    let k = ((b - a) / g) as i128 * p as i128 % (n / g) as i128;
+    Some(((a as i128 + m as i128 * k).rem_euclid(lcm as i128)) as i64)
+}
+
+fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "p=0,4 v=3,-3\np=6,3 v=-1,-3\np=10,3 v=-1,2\np=2,0 v=2,-1\np=0,0 v=1,3\np=3,0 v=-2,-2
+p=7,6 v=-1,-3\np=3,0 v=-1,-2\np=9,3 v=2,3\np=7,3 v=-1,2\np=2,4 v=2,-3\np=9,5 v=-3,-3\n";
+
+    #[test]
+    fn safety_factor_of_the_example() {
+        let swarm = Swarm::parse(
+            EXAMPLE,
+            Space {
+                width: 11,
+                height: 7,
+            },
+        );
+        assert_eq!(swarm.safety_factor(100), 12);
+    }
+
+    #[test]
+    fn safety_factor_splits_even_sizes_in_equal_halves() {
+        // robots standing still on both sides of the middle of a 10 by 4 space, none of them may be skipped
+        let input = "p=4,1 v=0,0\np=5,1 v=0,0\np=5,1 v=0,0\np=4,2 v=0,0\np=4,2 v=0,0\np=4,2 v=0,0\np=9,3 v=0,0\n";
+        let swarm = Swarm::parse(
+            input,
+            Space {
+                width: 10,
+                height: 4,
+            },
+        );
+        // top left 1, top right 2, bottom left 3, bottom right 1
+        assert_eq!(swarm.safety_factor(0), 6);
+    }
+
+    #[test]
+    fn safety_factor_skips_the_middle_of_odd_sizes() {
+        let input =
+            "p=5,0 v=0,0\np=0,3 v=0,0\np=0,0 v=0,0\np=10,0 v=0,0\np=0,6 v=0,0\np=10,6 v=0,0\n";
+        let swarm = Swarm::parse(
+            input,
+            Space {
+                width: 11,
+                height: 7,
+            },
+        );
+        assert_eq!(swarm.safety_factor(0), 1);
+    }
+}
@@ -1,8 +1,12 @@
 ﻿use aoc24_tools::*;
-use std::collections::HashMap;
-use std::fs::read_to_string;
+use replay::Replay;
+use std::fs::{read_to_string, write};
+use std::io::{stdin, BufRead};
 use std::str::Lines;
-use Object::*;
+use warehouse::{format_moves, Direction, Warehouse};
+
+mod replay;
+mod warehouse;
 
 const DAY: u8 = 15;
 
@@ -12,377 +16,162 @@ fn main() {
 
     let path = "input.txt";
     let data = read_to_string(path).unwrap();
-    let (part1, part2, warehouse, robot) = measure_total!({ solve(&data) });
+    let (part1, part2, warehouse) = measure_total!({ solve(&data) });
 
-    warehouse.print(&robot);
+    warehouse.print();
     println!("Sum of boxes' GPS in 1st warehouse (Part 1): {part1}");
     println!("Sum of boxes' GPS in 2nd warehouse (Part 2): {part2}");
 
     print_summary(DAY);
-}
 
-fn solve(input: &str) -> (u32, u32, Warehouse, Point) {
-    let (mut robot, mut warehouse, moves) = measure!({ parse(input) }, "parse");
-    let part1 = measure!(
-        { execute_robot_movements(&mut robot, &mut warehouse, &moves) },
-        "1st,normal"
-    );
-    let (mut robot, mut warehouse) = measure!({ parse_wide(input) }, "parse_wide");
-    let part2 = measure!(
-        { execute_robot_movements_on_wide_map(&mut robot, &mut warehouse, &moves) },
-        "2nd,wide"
-    );
-    (part1, part2, warehouse, robot)
-}
-
-fn execute_robot_movements(
-    robot: &mut Point,
-    warehouse: &mut Warehouse,
-    moves: &Vec<Direction>,
-) -> u32 {
-    for robot_move in moves.into_iter() {
-        if let Some(destination) = try_move(robot, &mut warehouse.map, robot_move) {
-            robot.move_to(&destination);
-        }
-    }
-    warehouse.calculate_gps_sum()
-}
-
-fn try_move(
-    location: &Point,
-    warehouse_map: &mut HashMap<Point, Object>,
-    direction: &Direction,
-) -> Option<Point> {
-    let next_location = location.get(direction);
-    if let Some(object) = warehouse_map.get(&next_location) {
-        match object {
-            Wall => return None, //stop on wall
-            Box(_) => {
-                // let's try to move the box
-                let moved_box_location = try_move(&next_location, warehouse_map, direction)?;
-                warehouse_map
-                    .remove(&next_location)
-                    .and_then(|a_box| warehouse_map.insert(moved_box_location, a_box));
-            }
-        }
-    }
-    // empty spot or created one.
-    Some(next_location)
-}
-
-fn execute_robot_movements_on_wide_map(
-    robot: &mut Point,
-    warehouse: &mut Warehouse,
-    moves: &Vec<Direction>,
-) -> u32 {
-    for robot_move in moves.into_iter() {
-        if let Some(moved_robot_location) =
-            move_robot_on_wide_map(robot, &mut warehouse.map, robot_move)
-        {
-            robot.move_to(&moved_robot_location);
-        }
-    }
-
-    warehouse.calculate_gps_sum()
-}
-
-fn move_robot_on_wide_map(
-    robot: &Point,
-    warehouse_map: &mut HashMap<Point, Object>,
-    direction: &Direction,
-) -> Option<Point> {
-    // maybe not optimal, but for more easy thinking about this, separate the left, right and up/down movements
-    match direction {
-        Direction::Left => try_move_left_wide(&robot, warehouse_map, direction),
-        Direction::Right => try_move_right_wide(&robot, 1, warehouse_map, direction),
-        _ => {
-            let robot = Vec::from([(Point::new(robot.x, robot.y), 1)]);
-            try_move_up_or_down_wide(&robot, warehouse_map, direction)?
-                .into_iter()
-                .next()
-        }
-    }
-}
-
-fn try_move_left_wide(
-    location: &Point,
-    warehouse_map: &mut HashMap<Point, Object>,
-    direction: &Direction,
-) -> Option<Point> {
-    // when moving left, account for that a boxes are registered one position further
-    let next_location = location.get(direction);
-    // check for wall (because boxes are wide, we should never find those)
-    if let Some(Wall) = warehouse_map.get(&next_location) {
-        return None;
-    }
-
-    // next check for box:
-    let check_box_location = next_location.get(direction);
-    if let Some(Box(_)) = warehouse_map.get(&check_box_location) {
-        // found a box, so lets check if the box can still move: (use None propagation operator ?)
-        let moved_box_location = try_move_left_wide(&check_box_location, warehouse_map, direction)?;
-        warehouse_map
-            .remove(&check_box_location)
-            .and_then(|a_box| warehouse_map.insert(moved_box_location, a_box));
-    }
-    Some(next_location)
-}
-
-fn try_move_right_wide(
-    location: &Point,
-    my_width: u8,
-    warehouse_map: &mut HashMap<Point, Object>,
-    direction: &Direction,
-) -> Option<Point> {
-    // when moving right, account for the width of the object we are currently checking
-    // a robot has width = 1, but a box has a width = 2
-    let next_location = location.get(direction);
-    let check_location = Point::new(location.x + my_width, location.y);
-
-    // check for wall or box
-    if let Some(object) = warehouse_map.get(&check_location) {
-        match object {
-            Wall => return None,
-            Box(box_width) => {
-                // check if the box can move, using its width (None propagations using ?)
-                let moved_box_location =
-                    try_move_right_wide(&check_location, *box_width, warehouse_map, direction)?;
-                warehouse_map
-                    .remove(&check_location)
-                    .and_then(|a_box| warehouse_map.insert(moved_box_location, a_box));
-            }
+    // optional: cargo run -r -- --warehouse small.txt [--scale 2]
+    if let Some(path) = get_argument("warehouse") {
+        let scale = get_argument("scale").map_or(1, |scale| scale.parse().unwrap());
+        let input = read_to_string(path).unwrap();
+        let (warehouse, moves) = parse(&input);
+        let mut warehouse = warehouse.scaled(scale);
+        let gps_sum = execute_robot_movements(&mut warehouse, &moves);
+        warehouse.print();
+        println!("Sum of boxes' GPS: {gps_sum}");
+    }
+
+    // optional: cargo run -r -- --widen small.txt --output small_wide.txt
+    if let Some(path) = get_argument("widen") {
+        let (warehouse, moves) = parse(&read_to_string(path).unwrap());
+        let output = get_argument("output").unwrap_or("wide.txt".to_string());
+        let wide = warehouse.widen();
+        if wide.can_be_written() {
+            write(&output, format!("{}\n{}\n", wide, format_moves(&moves))).unwrap();
+            println!("Saved the wide warehouse to {output}");
+        } else {
+            println!("Can't save the wide warehouse, the map can't show boxes that are pushed one way only");
         }
     }
-    Some(next_location)
-}
 
-fn try_move_up_or_down_wide(
-    locations: &Vec<(Point, u8)>,
-    warehouse_map: &mut HashMap<Point, Object>,
-    direction: &Direction,
-) -> Option<Vec<Point>> {
-    // receives one or more objects to move up or down, all are in the same row.
-    // when moving down/up: check also for boxes in x-1, and if so, keep in mind that boxes push 2 wide.
-    // also keep in mind, if one of the boxes can not move, nothing can move,
-    // that's why we collect all boxes before doing recursion
-    let offset_y: i16 = match direction {
-        Direction::Up => -1,
-        Direction::Down => 1,
-        _ => panic!("This method only supports up or down"),
+    // optional: cargo run -r -- --replay [--wide] [--seek 1234]
+    if has_flag("replay") {
+        let (narrow, moves) = parse(&data);
+        let warehouse = if has_flag("wide") {
+            parse_wide(&data)
+        } else {
+            narrow
+        };
+        let mut replay = Replay::new(warehouse, &moves);
+        replay.seek(get_argument("seek").map_or(0, |seek| seek.parse().unwrap()));
+        replay_in_terminal(&mut replay);
+    }
+}
+
+fn replay_in_terminal(replay: &mut Replay) {
+    let show = |replay: &Replay| {
+        replay.warehouse().print();
+        let last_move = replay.position().checked_sub(1).map(|i| &replay.log()[i]);
+        match last_move {
+            Some(last_move) => println!(
+                "Move {}/{}: robot {} went {:?}, shifting {} box(es){}",
+                replay.position(),
+                replay.len(),
+                last_move.robot,
+                last_move.direction,
+                last_move.moved.len().saturating_sub(1),
+                if last_move.moved.is_empty() {
+                    " (blocked)"
+                } else {
+                    ""
+                }
+            ),
+            None => println!("Move 0/{}", replay.len()),
+        }
+        println!(
+            "Sum of boxes' GPS: {}",
+            replay.warehouse().calculate_gps_sum()
+        );
+        println!("[enter] next, [p] previous, [g n] go to move n, [s file] save, [q] quit");
     };
-    let check_locations = locations.into_iter().flat_map(|(point, width)| {
-        (0..*width)
-            .map(move |offset_x| Point::new(point.x + offset_x, (point.y as i16 + offset_y) as u8))
-    });
 
-    let mut boxes = Vec::new();
-    for check_location in check_locations {
-        // check directly above for wall's and boxes:
-        if let Some(object) = warehouse_map.get(&check_location) {
-            match object {
-                Wall => {
-                    return None;
-                }
-                Box(width) => {
-                    boxes.push((check_location, *width)); // remember box, check them all at once!
-                }
+    show(replay);
+    for line in stdin().lock().lines() {
+        let line = line.unwrap();
+        let command: Vec<_> = line.split_whitespace().collect();
+        match command[..] {
+            [] | ["n"] => {
+                replay.redo();
             }
-        } else {
-            // check for a box, one to the left:
-            let check_for_box_location = Point::new(check_location.x - 1, check_location.y);
-            if let Some(Box(width)) = warehouse_map.get(&check_for_box_location) {
-                boxes.push((check_for_box_location, *width));
+            ["p"] => {
+                replay.undo();
             }
+            ["g", position] => replay.seek(position.parse().unwrap_or(replay.position())),
+            ["s", path] if !replay.warehouse().can_be_written() => {
+                println!("Can't save to {path}, the map can't show all of the boxes");
+                continue;
+            }
+            ["s", path] => {
+                // the map as it is now, with the moves that are still to come
+                let state = format!(
+                    "{}\n{}\n",
+                    replay.warehouse(),
+                    format_moves(&replay.remaining_moves())
+                );
+                write(path, state).unwrap();
+                println!("Saved to {path}");
+                continue;
+            }
+            ["q"] => break,
+            _ => println!("Unknown command '{line}'"),
         }
+        show(replay);
     }
+}
 
-    // when a box is found:
-    if !boxes.is_empty() {
-        // can the boxes be moved? (using none-propagation)
-        let new_locations = try_move_up_or_down_wide(&boxes, warehouse_map, direction)?;
-        for (new_location, (old_location, _)) in new_locations.into_iter().zip(boxes) {
-            warehouse_map
-                .remove(&old_location)
-                .and_then(|a_box| warehouse_map.insert(new_location, a_box));
-        }
-    }
-
-    // all locations allow a move, so respond with all new locations:
-    let new_locations = locations
-        .into_iter()
-        .map(|(point, _)| point.get(direction));
-    Some(new_locations.collect())
+fn solve(input: &str) -> (usize, usize, Warehouse) {
+    let (mut warehouse, moves) = measure!({ parse(input) }, "parse");
+    let part1 = measure!(
+        { execute_robot_movements(&mut warehouse, &moves) },
+        "1st,normal"
+    );
+    let mut warehouse = measure!({ parse_wide(input) }, "parse_wide");
+    let part2 = measure!(
+        { execute_robot_movements(&mut warehouse, &moves) },
+        "2nd,wide"
+    );
+    (part1, part2, warehouse)
 }
 
-fn parse_wide(input: &str) -> (Point, Warehouse) {
-    let mut map = HashMap::new();
-    let mut robot = Point::new(0, 0);
-    let mut y = 1u8; // start at 1, so we can use unsigned ints for all of this!
-    let mut width = 0;
-    for line in input.lines() {
-        if line.is_empty() {
-            break; //also stop on empty line
-        }
-        if width == 0 {
-            width = line.len() as u8 * 2;
-        }
-        for (x, c) in line.chars().enumerate() {
-            let x1 = (x * 2 + 1) as u8;
-            let x2 = x1 + 1;
-            if c == '#' {
-                map.insert(Point::new(x1, y), Wall);
-                map.insert(Point::new(x2, y), Wall);
-            } else if c == 'O' {
-                map.insert(Point::new(x1, y), Box(2));
-            } else if c == '@' {
-                robot = Point::new(x1, y);
-            }
+fn execute_robot_movements(warehouse: &mut Warehouse, moves: &[Direction]) -> usize {
+    // with more than one robot, every robot makes each move in turn
+    let robots = warehouse.robots().to_vec();
+    for &robot_move in moves {
+        for &robot in robots.iter() {
+            warehouse.move_object(robot, robot_move);
         }
-
-        y += 1;
     }
+    warehouse.calculate_gps_sum()
+}
 
-    (robot, Warehouse::new(map, width, y - 1))
+fn parse_wide(input: &str) -> Warehouse {
+    input.parse::<Warehouse>().unwrap().widen()
 }
 
-fn parse(input: &str) -> (Point, Warehouse, Vec<Direction>) {
-    let mut lines = input.lines().into_iter();
-    let mut map = HashMap::new();
-    let mut robot = Point::new(0, 0);
-    let mut y = 1u8; // start at 1, so we can use unsigned ints for all of this!
-    let mut width = 0;
-    while let Some(line) = lines.next() {
+fn parse(input: &str) -> (Warehouse, Vec<Direction>) {
+    let warehouse = input.parse().unwrap();
+    // skip the map, the moves come after the empty line
+    let mut lines = input.lines();
+    for line in lines.by_ref() {
         if line.is_empty() {
-            break; // empty line, so continue with directions
-        }
-        if width == 0 {
-            width = line.len() as u8;
-        }
-        for (x, c) in line.chars().enumerate() {
-            if c == '#' {
-                map.insert(Point::new((x + 1) as u8, y), Wall);
-            } else if c == 'O' {
-                map.insert(Point::new((x + 1) as u8, y), Box(1));
-            } else if c == '@' {
-                robot = Point::new((x + 1) as u8, y);
-            }
+            break;
         }
-        y += 1;
     }
-
-    (
-        robot,
-        Warehouse::new(map, width, y - 1),
-        parse_moves(&mut lines),
-    )
+    (warehouse, parse_moves(&mut lines))
 }
 
 fn parse_moves(lines: &mut Lines) -> Vec<Direction> {
     let mut moves = Vec::new();
-    while let Some(line) = lines.next() {
+    for line in lines {
         for c in line.chars() {
-            moves.push(match c {
-                '>' => Direction::Right,
-                '<' => Direction::Left,
-                '^' => Direction::Up,
-                'v' => Direction::Down,
-                _ => panic!("Unknown direction '{c}' found"),
-            })
+            moves.push(
+                Direction::try_from(c).unwrap_or_else(|_| panic!("Unknown direction '{c}' found")),
+            )
         }
     }
     moves
 }
-
-struct Warehouse {
-    map: HashMap<Point, Object>,
-    width: u8,
-    height: u8,
-}
-
-impl Warehouse {
-    fn new(map: HashMap<Point, Object>, width: u8, height: u8) -> Warehouse {
-        Warehouse { map, width, height }
-    }
-
-    fn print(&self, robot: &Point) {
-        for y in 1..self.height + 1 {
-            for x in 1..self.width + 1 {
-                let loc = Point::new(x, y);
-                if let Some(object) = self.map.get(&loc) {
-                    match object {
-                        Box(1) => print!("O"),
-                        Box(_) => print!("["),
-                        Wall => print!("#"),
-                    }
-                } else if loc == *robot {
-                    print!("\x1B[32m@\x1B[0m");
-                } else {
-                    let left = Direction::Left;
-                    let left = loc.get(&left);
-                    if let Some(Box(2)) = self.map.get(&left) {
-                        print!("]")
-                    } else {
-                        print!(".");
-                    }
-                }
-            }
-            println!();
-        }
-        println!();
-    }
-
-    fn calculate_gps_sum(&self) -> u32 {
-        self.map
-            .iter()
-            .filter_map(|(point, object)| match object {
-                Box(_) => Some(point.gps()),
-                _ => None,
-            })
-            .sum()
-    }
-}
-
-enum Object {
-    Wall,
-    Box(u8), //width
-}
-
-enum Direction {
-    Up,
-    Down,
-    Right,
-    Left,
-}
-
-#[derive(Hash, Eq, PartialEq)]
-struct Point {
-    x: u8,
-    y: u8,
-}
-
-impl Point {
-    fn new(x: u8, y: u8) -> Point {
-        Point { x, y }
-    }
-
-    fn get(&self, rhs: &Direction) -> Point {
-        match rhs {
-            Direction::Up => Point::new(self.x, self.y - 1),
-            Direction::Down => Point::new(self.x, self.y + 1),
-            Direction::Left => Point::new(self.x - 1, self.y),
-            Direction::Right => Point::new(self.x + 1, self.y),
-        }
-    }
-
-    fn move_to(&mut self, location: &Point) {
-        self.x = location.x;
-        self.y = location.y;
-    }
-
-    fn gps(&self) -> u32 {
-        // apply offset of -1, because we start every thing from 1
-        let x = self.x as u32 - 1;
-        let y = self.y as u32 - 1;
-        y * 100 + x
-    }
-}
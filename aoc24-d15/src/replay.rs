@@ -0,0 +1,149 @@
+use crate::warehouse::{Direction, Warehouse};
+
+/// A single move of a single robot, and everything it shifted (the robot first, nothing when it was blocked).
+pub struct LoggedMove {
+    pub robot: usize,
+    pub direction: Direction,
+    pub moved: Vec<usize>,
+}
+
+/// Plays the moves one at a time, and keeps a log of them, so the moves can be undone and redone.
+pub struct Replay {
+    warehouse: Warehouse,
+    // every robot makes each move in turn, so these are the moves of all robots one after the other
+    script: Vec<(usize, Direction)>,
+    log: Vec<LoggedMove>,
+    position: usize,
+}
+
+impl Replay {
+    pub fn new(warehouse: Warehouse, moves: &[Direction]) -> Replay {
+        let script = moves
+            .iter()
+            .flat_map(|&direction| {
+                warehouse
+                    .robots()
+                    .iter()
+                    .map(move |&robot| (robot, direction))
+            })
+            .collect();
+        Replay {
+            warehouse,
+            script,
+            log: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn warehouse(&self) -> &Warehouse {
+        &self.warehouse
+    }
+
+    /// The number of moves played so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.script.len()
+    }
+
+    /// The moves that are still to come, from the next move of the first robot on.
+    /// When some robots already moved in this round, that move of the other robots is skipped.
+    pub fn remaining_moves(&self) -> Vec<Direction> {
+        let robots = self.warehouse.robots().len().max(1);
+        self.script[self.position.next_multiple_of(robots)..]
+            .iter()
+            .step_by(robots)
+            .map(|&(_, direction)| direction)
+            .collect()
+    }
+
+    /// The moves played so far, and the moves that were undone (after the position), as far as they were played.
+    pub fn log(&self) -> &[LoggedMove] {
+        &self.log
+    }
+
+    /// Plays the next move, returns None at the end of the moves.
+    pub fn redo(&mut self) -> Option<&LoggedMove> {
+        let &(robot, direction) = self.script.get(self.position)?;
+        if let Some(logged) = self.log.get(self.position) {
+            // played before, so it shifts the same objects again
+            self.warehouse.shift(&logged.moved, logged.direction);
+        } else {
+            let moved = self
+                .warehouse
+                .move_object(robot, direction)
+                .unwrap_or_default();
+            self.log.push(LoggedMove {
+                robot,
+                direction,
+                moved,
+            });
+        }
+        self.position += 1;
+        self.log.get(self.position - 1)
+    }
+
+    /// Takes back the last move, returns None at the start.
+    pub fn undo(&mut self) -> Option<&LoggedMove> {
+        self.position = self.position.checked_sub(1)?;
+        let logged = &self.log[self.position];
+        self.warehouse
+            .shift(&logged.moved, logged.direction.opposite());
+        Some(logged)
+    }
+
+    /// Goes to the state after `position` moves (or the last move, when there aren't that many).
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.len());
+        while self.position < position {
+            self.redo();
+        }
+        while self.position > position {
+            self.undo();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_restore_the_gps_sums() {
+        let input = include_str!("../small.txt");
+        let (warehouse, moves) = input.split_once("\n\n").unwrap();
+        let warehouse: Warehouse = warehouse.parse().unwrap();
+        let moves: Vec<Direction> = moves
+            .chars()
+            .filter_map(|c| Direction::try_from(c).ok())
+            .collect();
+        let mut replay = Replay::new(warehouse, &moves);
+
+        let mut sums = Vec::from([replay.warehouse().calculate_gps_sum()]);
+        while replay.redo().is_some() {
+            sums.push(replay.warehouse().calculate_gps_sum());
+        }
+        assert_eq!(sums.last(), Some(&2028));
+
+        while replay.undo().is_some() {
+            assert_eq!(
+                replay.warehouse().calculate_gps_sum(),
+                sums[replay.position()]
+            );
+        }
+        assert_eq!(replay.position(), 0);
+        while replay.redo().is_some() {
+            assert_eq!(
+                replay.warehouse().calculate_gps_sum(),
+                sums[replay.position()]
+            );
+        }
+
+        for position in [3, 11, 0, replay.len(), 7] {
+            replay.seek(position);
+            assert_eq!(replay.warehouse().calculate_gps_sum(), sums[position]);
+        }
+    }
+}
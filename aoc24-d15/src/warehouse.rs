@@ -0,0 +1,555 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Right,
+    Left,
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Right => '>',
+            Direction::Left => '<',
+        };
+        write!(f, "{c}")
+    }
+}
+
+impl TryFrom<char> for Direction {
+    type Error = ();
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '^' => Ok(Direction::Up),
+            'v' => Ok(Direction::Down),
+            '>' => Ok(Direction::Right),
+            '<' => Ok(Direction::Left),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Writes the moves like the puzzle input does, in lines of 1000 moves.
+pub fn format_moves(moves: &[Direction]) -> String {
+    moves
+        .chunks(1000)
+        .map(|line| {
+            line.iter()
+                .map(|direction| direction.to_string())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub fn new(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+
+    fn get(&self, direction: Direction) -> Option<Point> {
+        let (dx, dy) = direction.offset();
+        Some(Point::new(
+            self.x.checked_add_signed(dx)?,
+            self.y.checked_add_signed(dy)?,
+        ))
+    }
+
+    fn gps(&self) -> usize {
+        self.y * 100 + self.x
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Wall,
+    Box,
+    Robot,
+}
+
+/// In which directions something can be pushed, by a robot or by anything a robot pushes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pushability {
+    Fixed,
+    Free,
+    Horizontal,
+    Vertical,
+}
+
+impl Pushability {
+    fn allows(&self, direction: Direction) -> bool {
+        match self {
+            Pushability::Fixed => false,
+            Pushability::Free => true,
+            Pushability::Horizontal => matches!(direction, Direction::Left | Direction::Right),
+            Pushability::Vertical => matches!(direction, Direction::Up | Direction::Down),
+        }
+    }
+}
+
+/// Anything in the warehouse, it covers a rectangle of tiles starting at its (top left) position.
+#[derive(Clone, Debug)]
+pub struct Object {
+    pub kind: Kind,
+    pub position: Point,
+    pub width: usize,
+    pub height: usize,
+    pub pushability: Pushability,
+}
+
+impl Object {
+    pub fn wall(position: Point) -> Object {
+        Object {
+            kind: Kind::Wall,
+            position,
+            width: 1,
+            height: 1,
+            pushability: Pushability::Fixed,
+        }
+    }
+
+    pub fn new_box(position: Point, width: usize, height: usize) -> Object {
+        Object {
+            kind: Kind::Box,
+            position,
+            width,
+            height,
+            pushability: Pushability::Free,
+        }
+    }
+
+    /// A robot can be pushed by other robots (and by the boxes they push), like a box.
+    pub fn robot(position: Point) -> Object {
+        Object {
+            kind: Kind::Robot,
+            position,
+            width: 1,
+            height: 1,
+            pushability: Pushability::Free,
+        }
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.height).flat_map(move |dy| {
+            (0..self.width).map(move |dx| Point::new(self.position.x + dx, self.position.y + dy))
+        })
+    }
+}
+
+/// The warehouse keeps every object, and for every tile which object (if any) is on it.
+#[derive(Clone)]
+pub struct Warehouse {
+    width: usize,
+    height: usize,
+    objects: Vec<Object>,
+    tiles: Vec<Option<usize>>,
+    robots: Vec<usize>,
+}
+
+impl Warehouse {
+    pub fn new(width: usize, height: usize) -> Warehouse {
+        Warehouse {
+            width,
+            height,
+            objects: Vec::new(),
+            tiles: vec![None; width * height],
+            robots: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the object, or None when it doesn't fit.
+    pub fn add(&mut self, object: Object) -> Option<usize> {
+        let fits = object.tiles().all(|tile| {
+            self.index(tile)
+                .is_some_and(|index| self.tiles[index].is_none())
+        });
+        if !fits {
+            return None;
+        }
+        let id = self.objects.len();
+        for tile in object.tiles() {
+            let index = self.index(tile).unwrap();
+            self.tiles[index] = Some(id);
+        }
+        if object.kind == Kind::Robot {
+            self.robots.push(id);
+        }
+        self.objects.push(object);
+        Some(id)
+    }
+
+    /// The ids of the robots, in the order they were added.
+    pub fn robots(&self) -> &[usize] {
+        &self.robots
+    }
+
+    pub fn object_at(&self, point: Point) -> Option<usize> {
+        self.tiles[self.index(point)?]
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        (point.x < self.width && point.y < self.height).then(|| point.y * self.width + point.x)
+    }
+
+    /// Moves an object one tile, pushing everything in the way.
+    /// Returns the ids of everything that moved (the object first), or None when something blocks the push,
+    /// in which case nothing moves at all.
+    pub fn move_object(&mut self, id: usize, direction: Direction) -> Option<Vec<usize>> {
+        let moving = self.push_graph(id, direction)?;
+        self.shift(&moving, direction);
+        Some(moving)
+    }
+
+    // Finds everything that has to move along (the push graph), without changing anything yet.
+    fn push_graph(&self, id: usize, direction: Direction) -> Option<Vec<usize>> {
+        let mut moving = Vec::from([id]);
+        let mut next = 0;
+        while let Some(&current) = moving.get(next) {
+            for tile in self.objects[current].tiles() {
+                let index = self.index(tile.get(direction)?)?;
+                match self.tiles[index] {
+                    Some(other) if other != current && !moving.contains(&other) => {
+                        if !self.objects[other].pushability.allows(direction) {
+                            return None;
+                        }
+                        moving.push(other);
+                    }
+                    _ => {}
+                }
+            }
+            next += 1;
+        }
+        Some(moving)
+    }
+
+    // Moves all objects at once: clear all their tiles first, so they don't overwrite each other.
+    pub(crate) fn shift(&mut self, ids: &[usize], direction: Direction) {
+        for &id in ids {
+            for tile in self.objects[id].tiles() {
+                let index = self.index(tile).unwrap();
+                self.tiles[index] = None;
+            }
+        }
+        for &id in ids {
+            let object = &mut self.objects[id];
+            object.position = object.position.get(direction).unwrap();
+            for tile in self.objects[id].tiles() {
+                let index = self.index(tile).unwrap();
+                self.tiles[index] = Some(id);
+            }
+        }
+    }
+
+    pub fn calculate_gps_sum(&self) -> usize {
+        self.objects
+            .iter()
+            .filter(|object| object.kind == Kind::Box)
+            .map(|object| object.position.gps())
+            .sum()
+    }
+
+    /// Makes every tile `factor` tiles wide: walls fill all of them, boxes get wider, robots stay on the first one.
+    pub fn scaled(&self, factor: usize) -> Warehouse {
+        let mut scaled = Warehouse::new(self.width * factor, self.height);
+        for object in self.objects.iter() {
+            let position = Point::new(object.position.x * factor, object.position.y);
+            match object.kind {
+                Kind::Wall => {
+                    for dx in 0..object.width * factor {
+                        scaled.add(Object::wall(Point::new(position.x + dx, position.y)));
+                    }
+                }
+                Kind::Box => {
+                    scaled.add(Object {
+                        position,
+                        width: object.width * factor,
+                        ..object.clone()
+                    });
+                }
+                Kind::Robot => {
+                    scaled.add(Object {
+                        position,
+                        ..object.clone()
+                    });
+                }
+            }
+        }
+        scaled
+    }
+
+    /// The second warehouse of the puzzle: everything twice as wide, except the robot.
+    pub fn widen(&self) -> Warehouse {
+        self.scaled(2)
+    }
+
+    /// Whether the map can be written and read back as the same warehouse. The map has no tall boxes,
+    /// and a wide box can always be pushed every way, so anything else would come back different.
+    pub fn can_be_written(&self) -> bool {
+        self.objects.iter().all(|object| {
+            object.kind != Kind::Box
+                || object.height == 1
+                    && (object.width == 1 || object.pushability == Pushability::Free)
+        })
+    }
+
+    pub fn print(&self) {
+        for c in self.to_string().chars() {
+            match c {
+                '@' => print!("\x1B[32m@\x1B[0m"), //use ANSI_escape_code to make it green
+                _ => print!("{c}"),
+            }
+        }
+        println!();
+    }
+}
+
+/// Writes the map like the puzzle input: `#` walls, `O` boxes, `[]` wide boxes (`[==]` for even wider ones),
+/// `@` robots and `.` for empty tiles. Boxes that only move sideways are `-`, and only up and down `|`.
+/// A tall box is drawn on every row it covers and a wide box always as `[]`, so reading those back is lossy,
+/// see [`Warehouse::can_be_written`].
+impl Display for Warehouse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = match self.object_at(Point::new(x, y)).map(|id| &self.objects[id]) {
+                    Some(object) => match object.kind {
+                        Kind::Wall => '#',
+                        Kind::Robot => '@',
+                        Kind::Box if object.width == 1 => match object.pushability {
+                            Pushability::Horizontal => '-',
+                            Pushability::Vertical => '|',
+                            _ => 'O',
+                        },
+                        Kind::Box if x == object.position.x => '[',
+                        Kind::Box if x == object.position.x + object.width - 1 => ']',
+                        Kind::Box => '=',
+                    },
+                    None => '.',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a map in the same format, up to the first empty line (where the moves start).
+/// All rows have to be equally long.
+impl FromStr for Warehouse {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let map: Vec<&str> = input.lines().take_while(|line| !line.is_empty()).collect();
+        let width = map.first().map_or(0, |line| line.len());
+        if map.iter().any(|line| line.len() != width) {
+            return Err(()); // every row has to be as wide as the warehouse
+        }
+        let mut warehouse = Warehouse::new(width, map.len());
+        for (y, line) in map.iter().enumerate() {
+            let mut wide_box: Option<usize> = None; // where the wide box on this line started
+            for (x, c) in line.chars().enumerate() {
+                let point = Point::new(x, y);
+                let object = match (c, wide_box) {
+                    ('=', Some(_)) => None,
+                    (']', Some(start)) => {
+                        wide_box = None;
+                        Some(Object::new_box(Point::new(start, y), x - start + 1, 1))
+                    }
+                    (_, Some(_)) => return Err(()), // a wide box that isn't closed
+                    ('[', None) => {
+                        wide_box = Some(x);
+                        None
+                    }
+                    ('#', None) => Some(Object::wall(point)),
+                    ('O', None) => Some(Object::new_box(point, 1, 1)),
+                    ('-', None) => Some(Object {
+                        pushability: Pushability::Horizontal,
+                        ..Object::new_box(point, 1, 1)
+                    }),
+                    ('|', None) => Some(Object {
+                        pushability: Pushability::Vertical,
+                        ..Object::new_box(point, 1, 1)
+                    }),
+                    ('@', None) => Some(Object::robot(point)),
+                    ('.', None) => None,
+                    _ => return Err(()),
+                };
+                if let Some(object) = object {
+                    warehouse.add(object).ok_or(())?;
+                }
+            }
+            if wide_box.is_some() {
+                return Err(());
+            }
+        }
+        Ok(warehouse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(warehouse: &Warehouse) -> Vec<Point> {
+        warehouse
+            .objects
+            .iter()
+            .map(|object| object.position)
+            .collect()
+    }
+
+    fn assert_tiles_match_objects(warehouse: &Warehouse) {
+        for (id, object) in warehouse.objects.iter().enumerate() {
+            for tile in object.tiles() {
+                assert_eq!(warehouse.object_at(tile), Some(id));
+            }
+        }
+        let covered: usize = warehouse.objects.iter().map(|o| o.width * o.height).sum();
+        assert_eq!(warehouse.tiles.iter().flatten().count(), covered);
+    }
+
+    #[test]
+    fn blocked_push_moves_nothing() {
+        // a row of boxes against a wall
+        let mut warehouse: Warehouse = "#######\n#.@OO##\n#######\n".parse().unwrap();
+        let before = positions(&warehouse);
+        assert_eq!(
+            warehouse.move_object(warehouse.robots()[0], Direction::Right),
+            None
+        );
+        assert_eq!(positions(&warehouse), before);
+
+        // wide boxes stacked like a pyramid, where only one of the top boxes is blocked
+        let input = "########\n##....##\n#.#...##\n#.[][].#\n#..[]..#\n#...@..#\n########\n";
+        let mut warehouse: Warehouse = input.parse().unwrap();
+        let before = positions(&warehouse);
+        assert_eq!(
+            warehouse.move_object(warehouse.robots()[0], Direction::Up),
+            None
+        );
+        assert_eq!(positions(&warehouse), before);
+        assert_tiles_match_objects(&warehouse);
+    }
+
+    #[test]
+    fn wide_and_tall_boxes_move_as_a_whole() {
+        let mut warehouse = Warehouse::new(8, 8);
+        let robot = warehouse.add(Object::robot(Point::new(1, 6))).unwrap();
+        let tall = warehouse
+            .add(Object::new_box(Point::new(1, 4), 1, 2))
+            .unwrap();
+        let square = warehouse
+            .add(Object::new_box(Point::new(0, 2), 2, 2))
+            .unwrap();
+        let wide = warehouse
+            .add(Object::new_box(Point::new(1, 1), 3, 1))
+            .unwrap();
+
+        let moved = warehouse.move_object(robot, Direction::Up).unwrap();
+        assert_eq!(moved, [robot, tall, square, wide]);
+        assert_eq!(warehouse.objects[tall].position, Point::new(1, 3));
+        assert_eq!(warehouse.objects[square].position, Point::new(0, 1));
+        assert_eq!(warehouse.objects[wide].position, Point::new(1, 0));
+        assert_tiles_match_objects(&warehouse);
+
+        // the wide box is against the edge now, so nothing can go up anymore
+        let before = positions(&warehouse);
+        assert_eq!(warehouse.move_object(robot, Direction::Up), None);
+        assert_eq!(positions(&warehouse), before);
+    }
+
+    fn round_trip(warehouse: &Warehouse) {
+        let text = warehouse.to_string();
+        let read: Warehouse = text.parse().unwrap();
+        assert_eq!(read.to_string(), text);
+        assert_eq!(positions(&read), {
+            // reading goes row by row, so the objects come back sorted on their position
+            let mut positions = positions(warehouse);
+            positions.sort_by_key(|p| (p.y, p.x));
+            positions
+        });
+        assert_eq!(read.calculate_gps_sum(), warehouse.calculate_gps_sum());
+    }
+
+    #[test]
+    fn maps_read_back_the_same() {
+        let (map, _) = include_str!("../small.txt").split_once("\n\n").unwrap();
+        let warehouse: Warehouse = map.parse().unwrap();
+        assert!(warehouse.can_be_written());
+        round_trip(&warehouse);
+        round_trip(&warehouse.widen());
+        round_trip(&warehouse.scaled(4));
+
+        // several robots, and boxes that only move one way
+        let robots: Warehouse = "#######\n#@.-.@#\n#.|.O.#\n#@....#\n#######\n"
+            .parse()
+            .unwrap();
+        assert_eq!(robots.robots().len(), 3);
+        round_trip(&robots);
+    }
+
+    #[test]
+    fn boxes_the_map_cant_show() {
+        let warehouse: Warehouse = "#####\n#@-.#\n#####\n".parse().unwrap();
+        assert!(warehouse.can_be_written());
+        // a wide box that only moves sideways would come back as one that moves every way
+        assert!(!warehouse.widen().can_be_written());
+
+        let mut warehouse = Warehouse::new(3, 3);
+        warehouse.add(Object::new_box(Point::new(1, 0), 1, 2));
+        assert!(!warehouse.can_be_written());
+    }
+
+    #[test]
+    fn malformed_maps() {
+        assert!("####\n#@.#\n###\n".parse::<Warehouse>().is_err()); // ragged rows
+        assert!("#####\n#@[.#\n#####\n".parse::<Warehouse>().is_err()); // a wide box that isn't closed
+        assert!("#####\n#@x.#\n#####\n".parse::<Warehouse>().is_err());
+    }
+
+    #[test]
+    fn robots_push_robots() {
+        let mut warehouse: Warehouse = "######\n#@@O.#\n######\n".parse().unwrap();
+        let (first, second) = (warehouse.robots()[0], warehouse.robots()[1]);
+        assert_eq!(
+            warehouse
+                .move_object(first, Direction::Right)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(warehouse.objects[second].position, Point::new(3, 1));
+        assert_eq!(warehouse.move_object(first, Direction::Right), None);
+    }
+}
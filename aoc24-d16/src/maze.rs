@@ -0,0 +1,379 @@
+use colored::Colorize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    fn clockwise(&self) -> Direction {
+        Direction::ALL[(*self as usize + 1) % 4]
+    }
+
+    fn counterclockwise(&self) -> Direction {
+        Direction::ALL[(*self as usize + 3) % 4]
+    }
+}
+
+impl TryFrom<&str> for Direction {
+    type Error = ();
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        match text.to_lowercase().as_str() {
+            "n" | "north" => Ok(Direction::North),
+            "e" | "east" => Ok(Direction::East),
+            "s" | "south" => Ok(Direction::South),
+            "w" | "west" => Ok(Direction::West),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What a reindeer can do: step forward, or rotate 90 degrees (counter)clockwise in place.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Move {
+    Forward,
+    Left,
+    Right,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Move::Forward => 'F',
+            Move::Left => 'L',
+            Move::Right => 'R',
+        };
+        write!(f, "{c}")
+    }
+}
+
+/// The points a move costs, the puzzle uses 1 for a step and 1000 for a turn.
+#[derive(Clone, Copy, Debug)]
+pub struct CostModel {
+    pub step: usize,
+    pub turn: usize,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            step: 1,
+            turn: 1000,
+        }
+    }
+}
+
+pub struct Maze {
+    pub width: usize,
+    pub height: usize,
+    walls: Vec<bool>,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl Maze {
+    pub fn parse(input: &str) -> Maze {
+        let lines: Vec<&str> = input.lines().filter(|line| !line.is_empty()).collect();
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut maze = Maze {
+            width,
+            height: lines.len(),
+            walls: vec![false; width * lines.len()],
+            start: (0, 0),
+            end: (0, 0),
+        };
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '#' => maze.walls[y * width + x] = true,
+                    'S' => maze.start = (x, y),
+                    'E' => maze.end = (x, y),
+                    _ => {}
+                }
+            }
+        }
+        maze
+    }
+
+    fn is_wall(&self, x: usize, y: usize) -> bool {
+        self.walls[y * self.width + x]
+    }
+
+    // A state is a tile together with the direction the reindeer faces: (y * width + x) * 4 + direction.
+    fn state(&self, (x, y): (usize, usize), direction: Direction) -> usize {
+        (y * self.width + x) * 4 + direction as usize
+    }
+
+    fn tile(&self, state: usize) -> (usize, usize) {
+        (state / 4 % self.width, state / 4 / self.width)
+    }
+
+    fn direction(&self, state: usize) -> Direction {
+        Direction::ALL[state % 4]
+    }
+
+    // The states a move leads to, and what that move is. Walking off the map counts as hitting a wall.
+    fn moves(&self, state: usize) -> impl Iterator<Item = (usize, Move)> + '_ {
+        let (x, y) = self.tile(state);
+        let direction = self.direction(state);
+        let ahead = match direction {
+            Direction::North => y.checked_sub(1).map(|y| (x, y)),
+            Direction::East => (x + 1 < self.width).then_some((x + 1, y)),
+            Direction::South => (y + 1 < self.height).then_some((x, y + 1)),
+            Direction::West => x.checked_sub(1).map(|x| (x, y)),
+        };
+        let forward = ahead
+            .filter(|&(x, y)| !self.is_wall(x, y))
+            .map(|tile| (self.state(tile, direction), Move::Forward));
+        forward.into_iter().chain([
+            (self.state((x, y), direction.counterclockwise()), Move::Left),
+            (self.state((x, y), direction.clockwise()), Move::Right),
+        ])
+    }
+
+    /// Finds all cheapest routes from the start to the end, starting in the given direction.
+    /// Dijkstra's algorithm keeps, for every state, the states it was cheapest reached from instead of whole paths.
+    /// Returns None when the end can't be reached.
+    pub fn solve(&self, costs: CostModel, facing: Direction) -> Option<BestPaths> {
+        // with free moves, the reindeer could go round in circles forever on a best route
+        assert!(
+            costs.step > 0 && costs.turn > 0,
+            "every move has to cost something"
+        );
+        let states = self.width * self.height * 4;
+        let mut scores = vec![usize::MAX; states];
+        let mut predecessors: Vec<Vec<(usize, Move)>> = vec![Vec::new(); states];
+        let mut order = Vec::new(); // the states in the order they were settled, so with increasing score
+        let start = self.state(self.start, facing);
+        scores[start] = 0;
+        let mut queue = BinaryHeap::from([Reverse((0, start))]);
+        while let Some(Reverse((score, state))) = queue.pop() {
+            if score > scores[state] {
+                continue; // already settled with a lower score
+            }
+            order.push(state);
+            for (next, movement) in self.moves(state) {
+                let next_score = score
+                    + match movement {
+                        Move::Forward => costs.step,
+                        Move::Left | Move::Right => costs.turn,
+                    };
+                if next_score < scores[next] {
+                    scores[next] = next_score;
+                    predecessors[next].clear();
+                    predecessors[next].push((state, movement));
+                    queue.push(Reverse((next_score, next)));
+                } else if next_score == scores[next] {
+                    predecessors[next].push((state, movement));
+                }
+            }
+        }
+
+        let score = Direction::ALL
+            .iter()
+            .map(|&direction| scores[self.state(self.end, direction)])
+            .min()
+            .filter(|&score| score < usize::MAX)?;
+        let ends: Vec<usize> = Direction::ALL
+            .iter()
+            .map(|&direction| self.state(self.end, direction))
+            .filter(|&state| scores[state] == score)
+            .collect();
+
+        // count the best routes to every state from the start, and from every state to the end
+        let mut from_start = vec![0u128; states];
+        from_start[start] = 1;
+        for &state in order.iter() {
+            for &(previous, _) in predecessors[state].iter() {
+                from_start[state] += from_start[previous];
+            }
+        }
+        let mut to_end = vec![0u128; states];
+        for &end in ends.iter() {
+            to_end[end] = 1;
+        }
+        for &state in order.iter().rev() {
+            for &(previous, _) in predecessors[state].iter() {
+                to_end[previous] += to_end[state];
+            }
+        }
+
+        // With moves that all cost something, a best route never comes back to a tile it left,
+        // so the routes that cross a tile are those that start there or step onto it.
+        let mut tile_routes = vec![0u128; self.width * self.height];
+        for &state in order.iter() {
+            let stepped_on: u128 = predecessors[state]
+                .iter()
+                .filter(|&&(_, movement)| movement == Move::Forward)
+                .map(|&(previous, _)| from_start[previous])
+                .sum();
+            tile_routes[state / 4] += stepped_on * to_end[state];
+        }
+        tile_routes[start / 4] = ends.iter().map(|&end| from_start[end]).sum();
+
+        Some(BestPaths {
+            score,
+            start,
+            ends,
+            predecessors,
+            tile_routes,
+        })
+    }
+
+    /// Prints the maze with the number of best routes that cross every tile (`+` for 10 or more),
+    /// the more routes, the brighter the tile.
+    pub fn print(&self, best_paths: &BestPaths) {
+        let all_routes = best_paths.route_count();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let routes = best_paths.tile_routes[y * self.width + x];
+                let tile = match routes {
+                    _ if self.is_wall(x, y) => "#".to_string(),
+                    0 => ".".to_string(),
+                    1..=9 => routes.to_string(),
+                    _ => "+".to_string(),
+                };
+                if routes == 0 {
+                    print!("{tile}");
+                } else {
+                    let brightness = (80 + 175 * routes / all_routes) as u8;
+                    print!("{}", tile.black().on_truecolor(brightness, brightness, 0));
+                }
+            }
+            println!();
+        }
+    }
+}
+
+/// All cheapest routes through the maze, kept as the cheapest ways to reach each state.
+pub struct BestPaths {
+    pub score: usize,
+    start: usize,
+    // the states at the end (one per direction) that are reached with the best score
+    ends: Vec<usize>,
+    predecessors: Vec<Vec<(usize, Move)>>,
+    // the number of best routes that cross every tile
+    tile_routes: Vec<u128>,
+}
+
+impl BestPaths {
+    pub fn route_count(&self) -> u128 {
+        self.tile_routes[self.start / 4]
+    }
+
+    /// The number of tiles on at least one of the best routes.
+    pub fn tile_count(&self) -> usize {
+        self.tile_routes
+            .iter()
+            .filter(|&&routes| routes > 0)
+            .count()
+    }
+
+    /// Every best route as a list of moves. There can be very many, so they are only found when asked for.
+    pub fn routes(&self) -> impl Iterator<Item = Vec<Move>> + '_ {
+        // walk back from the end, every entry is a state and the moves from there to the end (reversed)
+        let mut stack: Vec<(usize, Vec<Move>)> =
+            self.ends.iter().map(|&end| (end, Vec::new())).collect();
+        std::iter::from_fn(move || {
+            while let Some((state, moves)) = stack.pop() {
+                if state == self.start {
+                    return Some(moves.into_iter().rev().collect());
+                }
+                for &(previous, movement) in self.predecessors[state].iter() {
+                    let mut moves = moves.clone();
+                    moves.push(movement);
+                    stack.push((previous, moves));
+                }
+            }
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_EXAMPLE: &str = "###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############
+";
+
+    fn solve_example(input: &str) -> BestPaths {
+        Maze::parse(input)
+            .solve(CostModel::default(), Direction::East)
+            .unwrap()
+    }
+
+    fn route_strings(best_paths: &BestPaths) -> Vec<String> {
+        let mut routes: Vec<String> = best_paths
+            .routes()
+            .map(|route| route.iter().map(|m| m.to_string()).collect())
+            .collect();
+        routes.sort();
+        routes
+    }
+
+    #[test]
+    fn first_example() {
+        let best_paths = solve_example(FIRST_EXAMPLE);
+        assert_eq!(best_paths.score, 7036);
+        assert_eq!(best_paths.tile_count(), 45);
+        assert_eq!(best_paths.route_count(), 3);
+    }
+
+    #[test]
+    fn second_example() {
+        let best_paths = solve_example(include_str!("../example.txt"));
+        assert_eq!(best_paths.score, 11048);
+        assert_eq!(best_paths.tile_count(), 64);
+        assert_eq!(best_paths.route_count(), 2);
+        assert_eq!(best_paths.routes().count(), 2);
+    }
+
+    #[test]
+    fn routes_as_moves() {
+        // the wall ahead forces a turn, then there is only one way round
+        let best_paths = solve_example("#####\n#...#\n#S#E#\n#####\n");
+        assert_eq!(best_paths.score, 3004);
+        assert_eq!(route_strings(&best_paths), ["LFRFFRF"]);
+
+        // around the wall in the middle, over the top or the bottom at the same cost
+        let best_paths = solve_example("#######\n#.....#\n#S###E#\n#.....#\n#######\n");
+        assert_eq!(best_paths.score, 3006);
+        assert_eq!(best_paths.route_count(), 2);
+        assert_eq!(best_paths.tile_count(), 12);
+        assert_eq!(route_strings(&best_paths), ["LFRFFFFRF", "RFLFFFFLF"]);
+    }
+
+    #[test]
+    fn unreachable_end() {
+        let maze = Maze::parse("#####\n#S#E#\n#####\n");
+        assert!(maze.solve(CostModel::default(), Direction::East).is_none());
+    }
+}
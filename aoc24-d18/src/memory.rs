@@ -0,0 +1,414 @@
+use crate::Point;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+
+const NEVER: usize = usize::MAX;
+
+/// Where to go: from the start to whichever goal is nearest, optionally moving diagonally as well.
+/// A diagonal move may squeeze between two corrupted tiles.
+pub struct Routing {
+    pub start: Point,
+    pub goals: Vec<Point>,
+    pub diagonal: bool,
+}
+
+/// The memory space with all bytes that are going to fall, so it can be looked at after any number of them.
+/// It keeps a few numbers per tile, so the size is only limited by memory.
+pub struct MemorySpace {
+    width: usize,
+    height: usize,
+    bytes: Vec<Point>,
+    // for every tile, the index of the first byte that falls on it (or NEVER)
+    fallen_at: Vec<usize>,
+}
+
+impl MemorySpace {
+    pub fn parse(input: &str, width: usize, height: usize) -> MemorySpace {
+        let mut fallen_at = vec![NEVER; width * height];
+        let bytes: Vec<Point> = input
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (x, y) = line.split_once(',').unwrap();
+                Point::new(x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect();
+        for (i, byte) in bytes.iter().enumerate().rev() {
+            assert!(
+                (byte.x as usize) < width && (byte.y as usize) < height,
+                "byte {i} ({byte}) falls outside the memory space"
+            );
+            fallen_at[byte.y as usize * width + byte.x as usize] = i;
+        }
+        MemorySpace {
+            width,
+            height,
+            bytes,
+            fallen_at,
+        }
+    }
+
+    pub fn bytes(&self) -> &[Point] {
+        &self.bytes
+    }
+
+    fn index(&self, point: &Point) -> usize {
+        assert!(
+            (point.x as usize) < self.width && (point.y as usize) < self.height,
+            "{point} is outside the memory space"
+        );
+        point.y as usize * self.width + point.x as usize
+    }
+
+    fn point(&self, index: usize) -> Point {
+        Point::new((index % self.width) as u32, (index / self.width) as u32)
+    }
+
+    fn is_safe(&self, index: usize, fallen: usize) -> bool {
+        self.fallen_at[index] >= fallen
+    }
+
+    fn neighbours(&self, index: usize, diagonal: bool) -> impl Iterator<Item = usize> {
+        let (x, y, width, height) = (
+            index % self.width,
+            index / self.width,
+            self.width,
+            self.height,
+        );
+        let (up, left, down, right) = (y > 0, x > 0, y + 1 < height, x + 1 < width);
+        [
+            up.then(|| index - width),
+            left.then(|| index - 1),
+            down.then(|| index + width),
+            right.then(|| index + 1),
+        ]
+        .into_iter()
+        .chain(
+            [
+                (up && left).then(|| index - width - 1),
+                (up && right).then(|| index - width + 1),
+                (down && left).then(|| index + width - 1),
+                (down && right).then(|| index + width + 1),
+            ]
+            .into_iter()
+            .filter(move |_| diagonal),
+        )
+        .flatten()
+    }
+
+    /// The shortest path (start and goal included) to the nearest goal after a number of bytes have fallen,
+    /// using breadth first search.
+    pub fn find_path(&self, fallen: usize, routing: &Routing) -> Option<Vec<Point>> {
+        let start = self.index(&routing.start);
+        let mut is_goal = vec![false; self.fallen_at.len()];
+        for goal in routing.goals.iter() {
+            is_goal[self.index(goal)] = true;
+        }
+        if !self.is_safe(start, fallen) {
+            return None;
+        }
+        let mut previous = vec![NEVER; self.fallen_at.len()];
+        previous[start] = start;
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            if is_goal[current] {
+                let mut path = Vec::from([self.point(current)]);
+                let mut index = current;
+                while index != start {
+                    index = previous[index];
+                    path.push(self.point(index));
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbour in self.neighbours(current, routing.diagonal) {
+                if previous[neighbour] == NEVER && self.is_safe(neighbour, fallen) {
+                    previous[neighbour] = current;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    /// The index of the first byte that cuts all goals off from the start, None when that never happens.
+    /// Goes back in time: start with all bytes fallen, and take them away again, last one first,
+    /// joining the safe tiles with union-find until the start and a goal are connected.
+    pub fn find_blockade(&self, routing: &Routing) -> Option<usize> {
+        let start = self.index(&routing.start);
+        let goals: Vec<usize> = routing.goals.iter().map(|goal| self.index(goal)).collect();
+        let mut regions = UnionFind::new(self.fallen_at.len());
+        let mut safe: Vec<bool> = self.fallen_at.iter().map(|&i| i == NEVER).collect();
+        for index in 0..safe.len() {
+            if safe[index] {
+                for neighbour in self.neighbours(index, routing.diagonal) {
+                    if safe[neighbour] {
+                        regions.union(index, neighbour);
+                    }
+                }
+            }
+        }
+        let connected = |regions: &mut UnionFind, safe: &[bool]| {
+            safe[start]
+                && goals
+                    .iter()
+                    .any(|&goal| safe[goal] && regions.find(start) == regions.find(goal))
+        };
+        if connected(&mut regions, &safe) {
+            return None;
+        }
+        for (i, byte) in self.bytes.iter().enumerate().rev() {
+            let index = self.index(byte);
+            if self.fallen_at[index] != i {
+                continue; // the tile was already corrupted by an earlier byte
+            }
+            safe[index] = true;
+            for neighbour in self.neighbours(index, routing.diagonal) {
+                if safe[neighbour] {
+                    regions.union(index, neighbour);
+                }
+            }
+            if connected(&mut regions, &safe) {
+                return Some(i);
+            }
+        }
+        None // blocked before any byte fell
+    }
+
+    /// The same, by binary search over the number of fallen bytes: a path only ever gets blocked, never freed.
+    pub fn find_blockade_by_binary_search(&self, routing: &Routing) -> Option<usize> {
+        let (mut reachable, mut blocked) = (0, self.bytes.len());
+        if self.find_path(blocked, routing).is_some() {
+            return None;
+        }
+        self.find_path(reachable, routing)?; // blocked before any byte fell
+        while blocked - reachable > 1 {
+            let middle = (reachable + blocked) / 2;
+            match self.find_path(middle, routing) {
+                Some(_) => reachable = middle,
+                None => blocked = middle,
+            }
+        }
+        // after `blocked` bytes the path is gone, so the last of them did it
+        Some(blocked - 1)
+    }
+
+    /// The shortest path length after every number of fallen bytes.
+    /// A new path is only searched for when a byte falls on the current one, until no goal can be reached.
+    pub fn path_lengths(&self, routing: &Routing) -> PathLengths {
+        let mut lengths = Vec::with_capacity(self.bytes.len() + 1);
+        while lengths.len() <= self.bytes.len() {
+            let fallen = lengths.len();
+            let Some(path) = self.find_path(fallen, routing) else {
+                lengths.resize(self.bytes.len() + 1, None);
+                break;
+            };
+            // the path stays the shortest one until a byte falls on it
+            let hit = path
+                .iter()
+                .map(|point| self.fallen_at[self.index(point)])
+                .min()
+                .unwrap()
+                .min(self.bytes.len());
+            lengths.resize(hit + 1, Some(path.len() - 1));
+        }
+        PathLengths { lengths }
+    }
+
+    /// Which tiles can be reached from the start, after every number of fallen bytes.
+    /// A tile can be reached as long as there is a path to it on which no byte has fallen yet, so a tile stays
+    /// reachable up to the lowest byte index along its best path: a widest path search with a max-heap.
+    pub fn reachable_regions(&self, routing: &Routing) -> ReachableRegions {
+        let last = self.bytes.len();
+        let start = self.index(&routing.start);
+        let mut until = vec![0; self.fallen_at.len()];
+        let mut settled = vec![false; self.fallen_at.len()];
+        until[start] = self.fallen_at[start].min(last);
+        let mut queue = BinaryHeap::from([(until[start], start)]);
+        while let Some((reachable, current)) = queue.pop() {
+            if settled[current] {
+                continue;
+            }
+            settled[current] = true;
+            for neighbour in self.neighbours(current, routing.diagonal) {
+                let through = reachable.min(self.fallen_at[neighbour]);
+                if !settled[neighbour] && through > until[neighbour] {
+                    until[neighbour] = through;
+                    queue.push((through, neighbour));
+                }
+            }
+        }
+        ReachableRegions {
+            width: self.width,
+            until,
+            bytes: last,
+        }
+    }
+
+    pub fn print_with_path_and_block(
+        &self,
+        fallen: usize,
+        path: &HashSet<Point>,
+        block: Option<&Point>,
+    ) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let loc = Point::new(x as u32, y as u32);
+                if Some(&loc) == block {
+                    print!("\x1B[31mX\x1B[0m") //red
+                } else if !self.is_safe(self.index(&loc), fallen) {
+                    print!("\x1B[32m#\x1B[0m") //green
+                } else if path.contains(&loc) {
+                    print!("\x1B[33mO\x1B[0m") //yellow
+                } else {
+                    print!(".")
+                }
+            }
+            println!();
+        }
+        println!();
+    }
+}
+
+/// For every tile, up to how many fallen bytes it can be reached from the start.
+/// With no bytes fallen yet, every tile can be reached.
+pub struct ReachableRegions {
+    width: usize,
+    until: Vec<usize>,
+    bytes: usize,
+}
+
+impl ReachableRegions {
+    pub fn is_reachable(&self, point: &Point, fallen: usize) -> bool {
+        self.until[point.y as usize * self.width + point.x as usize] >= fallen
+    }
+
+    /// All tiles that can be reached after a number of fallen bytes.
+    pub fn region_after(&self, fallen: usize) -> HashSet<Point> {
+        self.until
+            .iter()
+            .enumerate()
+            .filter(|&(_, &until)| until >= fallen)
+            .map(|(index, _)| Point::new((index % self.width) as u32, (index / self.width) as u32))
+            .collect()
+    }
+
+    /// The number of tiles that can be reached, after every number of fallen bytes (from none to all).
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.bytes + 1];
+        for &until in self.until.iter() {
+            sizes[until] += 1;
+        }
+        // a tile that can be reached after n bytes can also be reached after fewer
+        for fallen in (0..self.bytes).rev() {
+            sizes[fallen] += sizes[fallen + 1];
+        }
+        sizes
+    }
+}
+
+/// The shortest path length after each number of fallen bytes, None once no goal can be reached anymore.
+pub struct PathLengths {
+    lengths: Vec<Option<usize>>,
+}
+
+impl PathLengths {
+    pub fn after(&self, fallen: usize) -> Option<usize> {
+        *self.lengths.get(fallen.min(self.lengths.len() - 1))?
+    }
+
+    /// Only the numbers of fallen bytes where the length changes, with the new length.
+    pub fn changes(&self) -> impl Iterator<Item = (usize, Option<usize>)> + '_ {
+        self.lengths
+            .iter()
+            .enumerate()
+            .filter(|&(fallen, length)| fallen == 0 || self.lengths[fallen - 1] != *length)
+            .map(|(fallen, &length)| (fallen, length))
+    }
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parents[x] != x {
+            self.parents[x] = self.parents[self.parents[x]]; // path halving
+            x = self.parents[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(diagonal: bool) -> (MemorySpace, Routing) {
+        let memory_space = MemorySpace::parse(include_str!("../example.txt"), 7, 7);
+        let routing = Routing {
+            start: Point::new(0, 0),
+            goals: Vec::from([Point::new(6, 6)]),
+            diagonal,
+        };
+        (memory_space, routing)
+    }
+
+    #[test]
+    fn shortest_path_after_12_bytes() {
+        let (memory_space, routing) = example(false);
+        assert_eq!(memory_space.find_path(12, &routing).unwrap().len() - 1, 22);
+        assert_eq!(memory_space.path_lengths(&routing).after(12), Some(22));
+    }
+
+    #[test]
+    fn first_byte_that_blocks_the_exit() {
+        let (memory_space, routing) = example(false);
+        assert_eq!(memory_space.find_blockade(&routing), Some(20));
+        assert_eq!(
+            memory_space.find_blockade_by_binary_search(&routing),
+            Some(20)
+        );
+        assert_eq!(memory_space.bytes()[20], Point::new(6, 1));
+    }
+
+    #[test]
+    fn reachable_tiles_after_every_byte() {
+        let (memory_space, routing) = example(false);
+        let regions = memory_space.reachable_regions(&routing);
+        let expected = [
+            49, 48, 47, 46, 45, 44, 43, 42, 41, 40, 39, 37, 36, 35, 34, 33, 31, 30, 28, 28, 27, 15,
+            13, 13, 13, 13,
+        ];
+        assert_eq!(regions.sizes(), expected);
+        assert_eq!(regions.region_after(21).len(), 15);
+        assert!(regions.is_reachable(&Point::new(6, 6), 20));
+        assert!(!regions.is_reachable(&Point::new(6, 6), 21));
+    }
+
+    #[test]
+    fn diagonal_route_squeezes_between_bytes() {
+        let (memory_space, routing) = example(true);
+        let path = memory_space.find_path(12, &routing).unwrap();
+        assert_eq!(path.len() - 1, 7);
+        assert_eq!(path.first(), Some(&Point::new(0, 0)));
+        assert_eq!(path.last(), Some(&Point::new(6, 6)));
+        for step in path.windows(2) {
+            assert!(step[0].x.abs_diff(step[1].x) <= 1 && step[0].y.abs_diff(step[1].y) <= 1);
+        }
+        // diagonally the bytes never cut the exit off
+        assert_eq!(memory_space.find_blockade(&routing), None);
+        assert_eq!(memory_space.path_lengths(&routing).after(25), Some(9));
+    }
+}
@@ -0,0 +1,198 @@
+const NONE: usize = usize::MAX;
+
+struct Node {
+    // the next node for every letter of the alphabet (or NONE)
+    children: Vec<usize>,
+    // the towel that ends at this node, if any
+    towel: Option<usize>,
+}
+
+/// All towels in a trie, built once and then used for every design.
+/// Walking the trie from a position in a design finds every towel that fits there in one go,
+/// so a design is solved with a single pass from its end to its start.
+pub struct TowelTrie<'a> {
+    towels: Vec<&'a str>,
+    // the index of every stripe colour in the alphabet (or NONE)
+    alphabet: [usize; 256],
+    nodes: Vec<Node>,
+}
+
+impl<'a> TowelTrie<'a> {
+    pub fn new(towels: &[&'a str]) -> TowelTrie<'a> {
+        let mut alphabet = [NONE; 256];
+        let mut size = 0;
+        for &byte in towels.iter().flat_map(|towel| towel.as_bytes()) {
+            if alphabet[byte as usize] == NONE {
+                alphabet[byte as usize] = size;
+                size += 1;
+            }
+        }
+        let mut trie = TowelTrie {
+            towels: towels.to_vec(),
+            alphabet,
+            nodes: Vec::from([Node {
+                children: vec![NONE; size],
+                towel: None,
+            }]),
+        };
+        for (id, towel) in towels.iter().enumerate() {
+            let mut node = 0;
+            for &byte in towel.as_bytes() {
+                let letter = trie.alphabet[byte as usize];
+                if trie.nodes[node].children[letter] == NONE {
+                    trie.nodes[node].children[letter] = trie.nodes.len();
+                    trie.nodes.push(Node {
+                        children: vec![NONE; size],
+                        towel: None,
+                    });
+                }
+                node = trie.nodes[node].children[letter];
+            }
+            trie.nodes[node].towel = Some(id);
+        }
+        trie
+    }
+
+    // Every towel that fits at the start of the design, as (towel, length), shortest first.
+    fn matches<'d>(&'d self, design: &'d [u8]) -> impl Iterator<Item = (usize, usize)> + 'd {
+        let mut node = 0;
+        design
+            .iter()
+            .enumerate()
+            .map_while(move |(i, &byte)| {
+                let letter = self.alphabet[byte as usize];
+                node = *self.nodes[node].children.get(letter)?;
+                (node != NONE).then(|| (self.nodes[node].towel, i + 1))
+            })
+            .filter_map(|(towel, length)| Some((towel?, length)))
+    }
+
+    // The number of arrangements for every end of the design (saturating at u64::MAX):
+    // ways[i] is the number of ways to make design[i..], so ways[0] is the answer for the whole design.
+    fn ways(&self, design: &str) -> Vec<u64> {
+        let design = design.as_bytes();
+        let mut ways = vec![0u64; design.len() + 1];
+        ways[design.len()] = 1;
+        for start in (0..design.len()).rev() {
+            ways[start] = self
+                .matches(&design[start..])
+                .fold(0u64, |sum, (_, length)| {
+                    sum.saturating_add(ways[start + length])
+                });
+        }
+        ways
+    }
+
+    pub fn is_possible(&self, design: &str) -> bool {
+        self.ways(design)[0] > 0
+    }
+
+    /// The number of ways to make the design, saturating at u64::MAX.
+    pub fn count_arrangements(&self, design: &str) -> u64 {
+        self.ways(design)[0]
+    }
+
+    /// The first arrangements of the design, with the shortest towels first.
+    /// Only towels after which the rest of the design can still be made are tried, so there's no dead end.
+    pub fn arrangements(&self, design: &str, limit: usize) -> Vec<Vec<&'a str>> {
+        let ways = self.ways(design);
+        let bytes = design.as_bytes();
+        let mut arrangements = Vec::new();
+        // depth first, with the towels of the current arrangement, where each of them ends,
+        // and the choices that are left at every position
+        let mut towels = Vec::new();
+        let mut positions = Vec::from([0]);
+        let mut choices = Vec::from([self.choices(bytes, &ways, 0)]);
+        while let (Some(options), Some(&position)) = (choices.last_mut(), positions.last()) {
+            if arrangements.len() >= limit {
+                break;
+            }
+            let next = if position == bytes.len() {
+                arrangements.push(towels.iter().map(|&id| self.towels[id]).collect());
+                None
+            } else {
+                options.pop()
+            };
+            match next {
+                Some(id) => {
+                    let position = position + self.towels[id].len();
+                    towels.push(id);
+                    positions.push(position);
+                    choices.push(self.choices(bytes, &ways, position));
+                }
+                None => {
+                    choices.pop();
+                    positions.pop();
+                    towels.pop();
+                }
+            }
+        }
+        arrangements
+    }
+
+    /// The arrangement with the lowest total cost, with the cost of every towel (None when it can't be used),
+    /// or None when the design can't be made. The same pass as counting, keeping the cheapest instead of the sum.
+    pub fn cheapest_arrangement(
+        &self,
+        design: &str,
+        cost: impl Fn(&str) -> Option<u64>,
+    ) -> Option<(u64, Vec<&'a str>)> {
+        let bytes = design.as_bytes();
+        let costs: Vec<Option<u64>> = self.towels.iter().map(|towel| cost(towel)).collect();
+        // the cheapest way to make design[i..], as its cost and the first towel
+        let mut cheapest: Vec<Option<(u64, usize)>> = vec![None; bytes.len() + 1];
+        cheapest[bytes.len()] = Some((0, NONE));
+        for start in (0..bytes.len()).rev() {
+            for (towel, length) in self.matches(&bytes[start..]) {
+                let (Some(towel_cost), Some((rest, _))) = (costs[towel], cheapest[start + length])
+                else {
+                    continue;
+                };
+                let total = towel_cost.saturating_add(rest);
+                if cheapest[start].is_none_or(|(best, _)| total < best) {
+                    cheapest[start] = Some((total, towel));
+                }
+            }
+        }
+
+        let (total, _) = cheapest[0]?;
+        let mut arrangement = Vec::new();
+        let mut position = 0;
+        while position < bytes.len() {
+            let (_, towel) = cheapest[position].unwrap();
+            arrangement.push(self.towels[towel]);
+            position += self.towels[towel].len();
+        }
+        Some((total, arrangement))
+    }
+
+    /// An arrangement with as few towels as possible.
+    pub fn fewest_towels(&self, design: &str) -> Option<Vec<&'a str>> {
+        let (_, arrangement) = self.cheapest_arrangement(design, |_| Some(1))?;
+        Some(arrangement)
+    }
+
+    /// The towels that can be made from (as few as possible of) the other towels, with how to make them.
+    /// They are made from shorter towels only, so all of them can be left out together and every design stays possible.
+    pub fn redundant_towels(&self) -> Vec<(&'a str, Vec<&'a str>)> {
+        self.towels
+            .iter()
+            .filter_map(|&towel| {
+                let (_, arrangement) =
+                    self.cheapest_arrangement(towel, |other| (other != towel).then_some(1))?;
+                Some((towel, arrangement))
+            })
+            .collect()
+    }
+
+    // The towels that fit at a position and leave a rest that can be made, in reverse, to pop from the end.
+    fn choices(&self, design: &[u8], ways: &[u64], position: usize) -> Vec<usize> {
+        let mut choices: Vec<usize> = self
+            .matches(&design[position..])
+            .filter(|&(_, length)| ways[position + length] > 0)
+            .map(|(towel, _)| towel)
+            .collect();
+        choices.reverse();
+        choices
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{Map, Point};
+use std::collections::BTreeMap;
+
+const UNREACHABLE: usize = usize::MAX;
+
+/// A cheat goes through walls from one spot on the racetrack to another, and saves some picoseconds.
+pub struct Cheat {
+    pub start: Point,
+    pub end: Point,
+    pub saving: usize,
+}
+
+/// The distance of every tile to one tile on the map, walking around the walls.
+pub struct DistanceField {
+    width: usize,
+    height: usize,
+    distances: Vec<usize>,
+}
+
+impl DistanceField {
+    pub fn get(&self, point: &Point) -> Option<usize> {
+        if point.x < 0 || point.y < 0 {
+            return None;
+        }
+        let (x, y) = (point.x as usize, point.y as usize);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let distance = self.distances[y * self.width + x];
+        (distance != UNREACHABLE).then_some(distance)
+    }
+}
+
+impl Map {
+    /// Breadth first search from one tile to every tile that can be reached.
+    pub fn distances_from(&self, from: &Point) -> DistanceField {
+        let mut field = DistanceField {
+            width: self.width,
+            height: self.height,
+            distances: vec![UNREACHABLE; self.width * self.height],
+        };
+        let mut current = Vec::from([from.clone()]);
+        let mut distance = 0;
+        while !current.is_empty() {
+            let mut next = Vec::new();
+            for point in current {
+                let index = point.y as usize * self.width + point.x as usize;
+                if field.distances[index] != UNREACHABLE {
+                    continue;
+                }
+                field.distances[index] = distance;
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let neighbour = Point::new(point.x + dx, point.y + dy);
+                    if self.is_safe(&neighbour) == Some(true) && field.get(&neighbour).is_none() {
+                        next.push(neighbour);
+                    }
+                }
+            }
+            current = next;
+            distance += 1;
+        }
+        field
+    }
+}
+
+/// The racetrack as the distance of every tile from the start, and to the end.
+/// That works for any maze, also with several routes and dead ends.
+pub struct RaceTrack {
+    from_start: DistanceField,
+    to_end: DistanceField,
+    // the fastest time without cheating
+    best_time: usize,
+}
+
+impl RaceTrack {
+    /// Returns None when the end can't be reached without cheating.
+    pub fn new(map: &Map) -> Option<RaceTrack> {
+        let from_start = map.distances_from(&map.start);
+        let to_end = map.distances_from(&map.end);
+        let best_time = from_start.get(&map.end)?;
+        Some(RaceTrack {
+            from_start,
+            to_end,
+            best_time,
+        })
+    }
+
+    pub fn best_time(&self) -> usize {
+        self.best_time
+    }
+
+    /// One of the fastest routes without cheating, from the start to the end.
+    pub fn best_path(&self, start: &Point) -> Vec<Point> {
+        let mut path = Vec::from([start.clone()]);
+        let mut current = start.clone();
+        // every step brings the end one closer, on any of the fastest routes
+        for remaining in (0..self.best_time).rev() {
+            current = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .into_iter()
+                .map(|(dx, dy)| Point::new(current.x + dx, current.y + dy))
+                .find(|next| self.to_end.get(next) == Some(remaining))
+                .unwrap();
+            path.push(current.clone());
+        }
+        path
+    }
+
+    /// Every cheat of at most `max_cheat_time` picoseconds that saves at least `min_saving` picoseconds.
+    /// A cheat is known by its start and end, so two cheats with the same start and end are the same cheat.
+    pub fn find_cheats(&self, max_cheat_time: usize, min_saving: usize) -> Vec<Cheat> {
+        let mut cheats = Vec::new();
+        self.for_each_cheat(max_cheat_time, min_saving, |start, end, saving| {
+            cheats.push(Cheat { start, end, saving })
+        });
+        cheats
+    }
+
+    /// The same, but only the number of cheats.
+    pub fn count_cheats(&self, max_cheat_time: usize, min_saving: usize) -> usize {
+        let mut count = 0;
+        self.for_each_cheat(max_cheat_time, min_saving, |_, _, _| count += 1);
+        count
+    }
+
+    // A cheat from a to b takes the fastest way to a, the cheat itself, and the fastest way from b to the end:
+    // from_start[a] + cheat time + to_end[b], which saves time when it beats the best time without cheating.
+    fn for_each_cheat(
+        &self,
+        max_cheat_time: usize,
+        min_saving: usize,
+        mut found: impl FnMut(Point, Point, usize),
+    ) {
+        let offsets = cheat_offsets(max_cheat_time);
+        let width = self.from_start.width;
+        for (index, &time_from_start) in self.from_start.distances.iter().enumerate() {
+            if time_from_start == UNREACHABLE || time_from_start + min_saving >= self.best_time {
+                continue; // too close to finish, no cheat can save enough
+            }
+            let cheat_from = Point::new((index % width) as i16, (index / width) as i16);
+            for &(dx, dy, cheat_time) in offsets.iter() {
+                let cheat_to = Point::new(cheat_from.x + dx, cheat_from.y + dy);
+                let Some(time_to_end) = self.to_end.get(&cheat_to) else {
+                    continue; // a wall, outside the map, or somewhere the end can't be reached from
+                };
+                let time = time_from_start + cheat_time + time_to_end;
+                if time < self.best_time && self.best_time - time >= min_saving {
+                    found(cheat_from.clone(), cheat_to, self.best_time - time);
+                }
+            }
+        }
+    }
+}
+
+/// The number of cheats for every saving, from the smallest saving to the largest.
+pub fn histogram(cheats: &[Cheat]) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for cheat in cheats {
+        *histogram.entry(cheat.saving).or_insert(0) += 1;
+    }
+    histogram
+}
+
+// Every spot a cheat can reach (a diamond around the start), with the time it takes.
+// A cheat of 1 picosecond doesn't go through a wall, so it isn't a cheat.
+fn cheat_offsets(max_cheat_time: usize) -> Vec<(i16, i16, usize)> {
+    let max = max_cheat_time as i16;
+    let mut offsets = Vec::new();
+    for dx in -max..=max {
+        let remaining = max - dx.abs();
+        for dy in -remaining..=remaining {
+            let cheat_time = (dx.abs() + dy.abs()) as usize;
+            if cheat_time >= 2 {
+                offsets.push((dx, dy, cheat_time));
+            }
+        }
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> RaceTrack {
+        RaceTrack::new(&Map::parse(include_str!("../example.txt"))).unwrap()
+    }
+
+    #[test]
+    fn cheats_of_2_picoseconds() {
+        let racetrack = example();
+        assert_eq!(racetrack.best_time(), 84);
+        let expected = BTreeMap::from([
+            (2, 14),
+            (4, 14),
+            (6, 2),
+            (8, 4),
+            (10, 2),
+            (12, 3),
+            (20, 1),
+            (36, 1),
+            (38, 1),
+            (40, 1),
+            (64, 1),
+        ]);
+        assert_eq!(histogram(&racetrack.find_cheats(2, 1)), expected);
+        assert_eq!(racetrack.count_cheats(2, 1), 44);
+    }
+
+    #[test]
+    fn cheats_of_20_picoseconds_saving_at_least_50() {
+        let racetrack = example();
+        let expected = BTreeMap::from([
+            (50, 32),
+            (52, 31),
+            (54, 29),
+            (56, 39),
+            (58, 25),
+            (60, 23),
+            (62, 20),
+            (64, 19),
+            (66, 12),
+            (68, 14),
+            (70, 12),
+            (72, 22),
+            (74, 4),
+            (76, 3),
+        ]);
+        assert_eq!(histogram(&racetrack.find_cheats(20, 50)), expected);
+        assert_eq!(racetrack.count_cheats(20, 50), 285);
+    }
+
+    #[test]
+    fn cheats_on_a_branching_track_with_a_dead_end() {
+        // two equally fast ways from S to (3, 3), then around the wall to E
+        // (4, 1) is a dead end, right next to E but 10 picoseconds from it on the track
+        let map = Map::parse(
+            "#######
+#S...##
+#.#.#E#
+#...#.#
+###.#.#
+###...#
+#######
+",
+        );
+        let racetrack = RaceTrack::new(&map).unwrap();
+        assert_eq!(racetrack.best_time(), 11);
+        assert_eq!(racetrack.best_path(&map.start).len(), 12);
+
+        let mut cheats: Vec<_> = racetrack
+            .find_cheats(2, 1)
+            .into_iter()
+            .map(|cheat| {
+                let (start, end) = (cheat.start, cheat.end);
+                ((start.x, start.y), (end.x, end.y), cheat.saving)
+            })
+            .collect();
+        cheats.sort();
+        // (3, 2) is 3 from the start and E is 2 away: 3 + 2 = 5 instead of 11
+        // the dead end (4, 1) is also 3 from the start, so a cheat from there to E saves the same
+        // (3, 3) is 4 from the start, (5, 3) 1 from E: 4 + 2 + 1 = 7
+        // (3, 4) is 5 from the start, (5, 4) 2 from E: 5 + 2 + 2 = 9
+        let expected = [
+            ((3, 2), (5, 2), 6),
+            ((3, 3), (5, 3), 4),
+            ((3, 4), (5, 4), 2),
+            ((4, 1), (5, 2), 6),
+        ];
+        assert_eq!(cheats, expected);
+        // a cheat into the dead end still has to walk all the way back out, that never saves anything
+        assert!(cheats.iter().all(|&(_, end, _)| end != (4, 1)));
+
+        let expected = BTreeMap::from([(2, 3), (4, 5), (6, 3)]);
+        assert_eq!(histogram(&racetrack.find_cheats(3, 1)), expected);
+        assert_eq!(racetrack.count_cheats(3, 1), 11);
+        assert_eq!(racetrack.count_cheats(3, 5), 3);
+    }
+}
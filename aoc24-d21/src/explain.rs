@@ -0,0 +1,100 @@
+use crate::{Keypad, KeypadChain};
+use std::collections::HashMap;
+
+// every level is printed on a single line, so keep the presses of the human readable
+const MAX_EXPLAIN_PRESSES: u64 = 2_000;
+
+impl KeypadChain {
+    /// Returns the presses on every keypad in the chain: first the human, then each robot, and finally the code itself.
+    /// Returns None when the code can't be typed, or when the chain is too deep to show the presses on a single line.
+    pub fn explain(&mut self, code: &str) -> Option<Vec<String>> {
+        if self.solve(code)?.presses > MAX_EXPLAIN_PRESSES {
+            return None;
+        }
+
+        // work upwards from the code: each level is the sequence the keypad above needs to type the level below
+        let mut levels = Vec::from([code.to_string()]);
+        let mut sequence = self.final_keypad.find_sequence_for_keypad_above(
+            code,
+            &self.directional,
+            self.robots,
+            &mut self.cache,
+        )?;
+        for depth in (0..self.robots).rev() {
+            let next_sequence = self.directional.find_sequence_for_keypad_above(
+                &sequence,
+                &self.directional,
+                depth,
+                &mut self.cache,
+            )?;
+            levels.push(sequence);
+            sequence = next_sequence;
+        }
+        levels.push(sequence);
+        levels.reverse();
+        Some(levels)
+    }
+}
+
+impl Keypad {
+    fn find_sequence_for_keypad_above(
+        &self,
+        button_sequence: &str,
+        directional: &Keypad,
+        depth: u8,
+        cache: &mut HashMap<(String, u8), u64>,
+    ) -> Option<String> {
+        let mut result = String::new();
+        let mut from_button = 'A';
+        for to_button in button_sequence.chars() {
+            let (best_sequence, _) = self.find_best_sequence_between_buttons(
+                from_button,
+                to_button,
+                directional,
+                depth,
+                cache,
+            )?;
+            result += &best_sequence;
+            from_button = to_button;
+        }
+        Some(result)
+    }
+}
+
+/// Puts every press right below the 'A' on the level above that triggered it, like the illustration in the puzzle:
+/// ```text
+/// you     : v<A<AA>>^AvAA<^A>Av<<A>>^AvA^Av<<A>>^AAv<A>A^A<A>Av<A<A>>^AAAvA<^A>A
+/// robot 1 :   v <<   A >>  ^ A   <   A > A   <   AA  v > A ^ A  v <   AAA >  ^ A
+/// robot 2 :          <       A       ^   A       ^^      >   A        vvv      A
+/// robot 3 :                  0           2                   9                 A
+/// ```
+pub fn render_explanation(levels: &[String]) -> String {
+    // the human presses every button on its own column
+    let mut columns: Vec<usize> = (0..levels[0].len()).collect();
+    let mut lines = Vec::new();
+    for (i, level) in levels.iter().enumerate() {
+        let name = if i == 0 {
+            "you".to_string()
+        } else {
+            format!("robot {i}")
+        };
+
+        let mut line = vec![' '; levels[0].len()];
+        for (button, &column) in level.chars().zip(columns.iter()) {
+            line[column] = button;
+        }
+        lines.push(format!(
+            "{name:8}: {}",
+            line.into_iter().collect::<String>().trim_end()
+        ));
+
+        // the n-th button pressed on the next level is caused by the n-th 'A' pressed on this level
+        columns = level
+            .chars()
+            .zip(columns.iter())
+            .filter(|(button, _)| *button == 'A')
+            .map(|(_, &column)| column)
+            .collect();
+    }
+    lines.join("\n")
+}
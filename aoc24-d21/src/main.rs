@@ -1,7 +1,10 @@
 ﻿use aoc24_tools::*;
+use explain::render_explanation;
 use std::collections::{BinaryHeap, HashMap};
 use std::fs::read_to_string;
 
+mod explain;
+
 const DAY: u8 = 21;
 
 fn main() {
@@ -14,21 +17,59 @@ fn main() {
     println!("Sum of the complexities using TWENTY-FIVE directional robots  (Part 2): {part2}");
 
     print_summary(DAY);
+
+    // optional: cargo run -r -- --sequences (or --explain) [--robots 3] [--codes codes.txt] [--keypad layout.txt] [--directional layout.txt]
+    if has_flag("sequences") || has_flag("explain") {
+        let codes = get_argument("codes").map_or(data, |path| read_to_string(path).unwrap());
+        print_sequences(&codes, has_flag("explain"));
+    }
 }
 
 fn solve(input: &str) -> (u64, u64) {
-    std::sync::Once::new().call_once(|| {
-        lazy_static::initialize(&DIRECTIONAL);
-        lazy_static::initialize(&NUMERIC);
-    });
-    let part1 = calculate_complexity_for_numeric_codes_with_two_directional_keypads(input);
-    let part2 = calculate_complexity_for_numeric_codes(input, 25);
+    let part1 = measure!(
+        { calculate_complexity_for_numeric_codes(input, 2) },
+        "part1"
+    );
+    let part2 = measure!(
+        { calculate_complexity_for_numeric_codes(input, 25) },
+        "part2"
+    );
     (part1, part2)
 }
 
-/****************************/
-/* Structs & static keypads */
-/****************************/
+fn print_sequences(codes: &str, explain: bool) {
+    let robots = get_argument("robots").map_or(2, |r| r.parse().unwrap());
+    let read_keypad = |name: &str, default: fn() -> Keypad| match get_argument(name) {
+        Some(path) => Keypad::from_text(&read_to_string(path).unwrap()),
+        None => default(),
+    };
+    let final_keypad = read_keypad("keypad", Keypad::numeric);
+    let directional = read_keypad("directional", Keypad::directional);
+
+    let mut chain = KeypadChain::new(final_keypad, directional, robots)
+        .expect("The directional keypad needs the buttons <, >, ^, v and A, all reachable");
+    for code in codes.lines() {
+        match chain.solve(code) {
+            Some(solution) => {
+                let sequence = solution
+                    .sequence
+                    .unwrap_or("(too long to show)".to_string());
+                println!("{code}: {} presses: {sequence}", solution.presses);
+                if explain {
+                    match chain.explain(code) {
+                        Some(levels) => println!("{}\n", render_explanation(&levels)),
+                        None => println!("(too many presses to explain)\n"),
+                    }
+                }
+            }
+            None => println!("{code}: can't be typed on this keypad"),
+        }
+    }
+}
+
+/***************************/
+/* Structs & keypad chains */
+/***************************/
 struct Keypad {
     // a keypad has a set of buttons, put them in a hashmap to be able to look up information about the buttons
     buttons: HashMap<char, SequenceCache>,
@@ -36,189 +77,102 @@ struct Keypad {
 
 struct SequenceCache {
     sequences_to_button: HashMap<char, Vec<String>>,
-    best_sequence_to_button: HashMap<char, String>,
 }
 
-lazy_static::lazy_static! {
-    static ref DIRECTIONAL: Keypad = Keypad::directional();
-    static ref NUMERIC: Keypad = Keypad::numeric();
-}
-
-/**********/
-/* Part 1 */
-/**********/
-fn calculate_complexity_for_numeric_codes_with_two_directional_keypads(codes: &str) -> u64 {
-    // Set up a cache to store how a sequence from 'A' to 'A' translates to the next directional keypad
-    let mut cache = HashMap::new();
-
-    let mut result = 0;
-    for code in codes.lines() {
-        let min_button_presses =
-            get_min_presses_for_numeric_code_with_two_directional_keypads(code, &mut cache);
-        result += calculate_complexity(code, min_button_presses);
-    }
-    result
-}
-
-fn get_min_presses_for_numeric_code_with_two_directional_keypads(
-    numeric_code: &str,
-    cache: &mut HashMap<String, String>,
-) -> u64 {
-    // step 1: find the possible routes on the numeric key pad
-    let button_sequences = NUMERIC.find_all_sequences(numeric_code);
-
-    // step 2: process the directional robots/keypads
-    let mut min_sequence_length = u64::MAX;
-    for button_sequence in button_sequences {
-        // let's only keep unique sequences and count how many there are:
-        let mut sub_sequence_counts = HashMap::new();
-        update_sub_sequence_count_with_sequence(&mut sub_sequence_counts, button_sequence, 1);
-
-        // apply now process the directional keypads between us and the numeric:
-        for _ in 0..2 {
-            sub_sequence_counts =
-                DIRECTIONAL.get_sub_sequences_for_next_robot(sub_sequence_counts, cache);
-        }
-
-        let sequence_length = sub_sequence_counts
-            .iter()
-            .map(|(fragment, amount)| (fragment.len() + 1) as u64 * amount)
-            .sum();
-        if sequence_length < min_sequence_length {
-            min_sequence_length = sequence_length;
-        }
-    }
-    min_sequence_length
+/// A human presses the first directional keypad, which is used to control a robot pressing the next directional keypad.
+/// After `robots` directional keypads, the last robot presses the buttons on the final keypad.
+struct KeypadChain {
+    final_keypad: Keypad,
+    directional: Keypad,
+    robots: u8,
+    // remembers the minimal number of presses for a sequence on a directional keypad at a certain depth in the chain
+    cache: HashMap<(String, u8), u64>,
 }
 
-fn update_sub_sequence_count_with_sequence(
-    sub_sequence_counts: &mut HashMap<String, u64>,
-    button_sequence: String,
-    count: u64,
-) {
-    // split the whole sequence up in parts from A to A (ignore the last, otherwise it's an empty sequence)
-    for sub_sequence in button_sequence[0..button_sequence.len() - 1].split('A') {
-        sub_sequence_counts
-            .entry(sub_sequence.to_string())
-            .and_modify(|total| *total += count)
-            .or_insert(count);
-    }
+struct ChainSolution {
+    presses: u64,
+    // with 25 robots the sequence is trillions of presses long, so it's only built when it's reasonably short
+    sequence: Option<String>,
 }
 
-fn calculate_complexity(input: &str, instruction_len: u64) -> u64 {
-    let value = input[0..3].parse::<u64>().unwrap();
-    instruction_len * value
-}
+const MAX_SEQUENCE_LENGTH: u64 = 1_000_000;
+const DIRECTIONAL_BUTTONS: [char; 5] = ['<', '>', '^', 'v', 'A'];
 
-impl Keypad {
-    fn find_all_sequences(&self, button_sequence: &str) -> Vec<String> {
-        // This method is shared between part 1 and part 2, because it works fine for the numeric keypad in both cases
-        let mut from_button = 'A';
-        let mut all_sequences = Vec::from([String::new()]);
-        for to_button in button_sequence.chars() {
-            let mut appended_sequences = Vec::new();
-            if from_button == to_button {
-                for prev_sequence in &all_sequences {
-                    appended_sequences.push(prev_sequence.clone() + "A");
-                }
-            } else {
-                let sequences_to_next_button =
-                    &self.buttons[&from_button].sequences_to_button[&to_button];
-                for prev_sequence in &all_sequences {
-                    for sequence_to_next_button in sequences_to_next_button {
-                        appended_sequences
-                            .push(prev_sequence.clone() + sequence_to_next_button + "A");
-                    }
-                }
+impl KeypadChain {
+    fn new(final_keypad: Keypad, directional: Keypad, robots: u8) -> Option<KeypadChain> {
+        // the robots can only be controlled when every direction can be reached from every other button
+        for from_button in DIRECTIONAL_BUTTONS {
+            for to_button in DIRECTIONAL_BUTTONS {
+                directional.get_button_sequences_between_buttons(from_button, to_button)?;
             }
-
-            all_sequences = appended_sequences;
-            from_button = to_button;
         }
-        all_sequences
-    }
 
-    fn get_sub_sequences_for_next_robot(
-        &self,
-        sub_sequences_with_counter: HashMap<String, u64>,
-        cache: &mut HashMap<String, String>,
-    ) -> HashMap<String, u64> {
-        let mut sub_sequence_count_result = HashMap::new();
-        for (sub_sequence, count) in sub_sequences_with_counter {
-            let sub_sequence_with_a = sub_sequence.to_string() + "A";
-
-            let min_sequence = self.get_min_sequence_for_sub_sequence(&sub_sequence_with_a, cache);
-            update_sub_sequence_count_with_sequence(
-                &mut sub_sequence_count_result,
-                min_sequence,
-                count,
-            );
-        }
-        sub_sequence_count_result
+        Some(KeypadChain {
+            final_keypad,
+            directional,
+            robots,
+            cache: HashMap::new(),
+        })
     }
 
-    fn get_min_sequence_for_sub_sequence(
-        &self,
-        sub_sequence: &str,
-        cache: &mut HashMap<String, String>,
-    ) -> String {
-        let key = sub_sequence.to_string();
-        if let Some(output) = cache.get(&key) {
-            return output.clone();
-        }
-
-        let mut sequence = String::new();
+    fn solve(&mut self, code: &str) -> Option<ChainSolution> {
+        // every button press on the final keypad ends with all robots above it pointing at 'A' again,
+        // so the best way to go from one button to the next doesn't depend on the other buttons in the code.
+        let mut presses = 0;
+        let mut best_sequences = Vec::new();
         let mut from_button = 'A';
-        for to_button in sub_sequence.chars() {
-            if from_button != to_button {
-                sequence += &self.buttons[&from_button].best_sequence_to_button[&to_button]
-            };
-            sequence.push('A');
+        for to_button in code.chars() {
+            let (best_sequence, best_presses) =
+                self.final_keypad.find_best_sequence_between_buttons(
+                    from_button,
+                    to_button,
+                    &self.directional,
+                    self.robots,
+                    &mut self.cache,
+                )?;
+            presses += best_presses;
+            best_sequences.push(best_sequence);
             from_button = to_button;
         }
 
-        cache.insert(key, sequence.clone());
-        sequence
+        let sequence = (presses <= MAX_SEQUENCE_LENGTH).then(|| {
+            best_sequences
+                .iter()
+                .map(|s| {
+                    self.directional
+                        .expand_button_presses(s, self.robots, &mut self.cache)
+                })
+                .collect()
+        });
+        Some(ChainSolution { presses, sequence })
     }
 }
 
-/**************************************/
-/* Part 2 with 25 directional keypads */
-/**************************************/
 fn calculate_complexity_for_numeric_codes(codes: &str, directional_keypads: u8) -> u64 {
-    // create a cache to remember sequences we already found
-    let mut cache = HashMap::new();
+    let mut chain = KeypadChain::new(
+        Keypad::numeric(),
+        Keypad::directional(),
+        directional_keypads,
+    )
+    .unwrap();
 
     let mut result = 0;
     for code in codes.lines() {
-        let min_button_presses =
-            get_min_button_presses_for_numeric_code(code, directional_keypads, &mut cache);
+        let min_button_presses = chain.solve(code).unwrap().presses;
         result += calculate_complexity(code, min_button_presses);
     }
     result
 }
 
-fn get_min_button_presses_for_numeric_code(
-    numeric_code: &str,
-    robots: u8,
-    cache: &mut HashMap<(String, u8), u64>,
-) -> u64 {
-    let button_sequences = NUMERIC.find_all_sequences(numeric_code);
-
-    let mut min_button_presses = u64::MAX;
-    for button_sequences in button_sequences {
-        let button_presses = DIRECTIONAL.find_min_button_presses(&button_sequences, robots, cache);
-        if button_presses < min_button_presses {
-            min_button_presses = button_presses;
-        }
-    }
-    min_button_presses
+fn calculate_complexity(input: &str, instruction_len: u64) -> u64 {
+    let value = input[0..3].parse::<u64>().unwrap();
+    instruction_len * value
 }
 
 impl Keypad {
     fn find_min_button_presses(
         &self,
-        button_sequence: &String,
+        button_sequence: &str,
         depth: u8,
         cache: &mut HashMap<(String, u8), u64>,
     ) -> u64 {
@@ -229,7 +183,7 @@ impl Keypad {
             return button_sequence.len() as u64;
         }
 
-        let key = (button_sequence.clone(), depth);
+        let key = (button_sequence.to_string(), depth);
         if let Some(&result) = cache.get(&key) {
             return result;
         }
@@ -237,18 +191,10 @@ impl Keypad {
         let mut total_sequence_length = 0;
         let mut from_button = 'A';
         for to_button in button_sequence.chars() {
-            // for each button to the next, find the shortest sequence that works
-            let mut min_sub_sequence_length = u64::MAX;
-            for button_sequence in self.get_button_sequences_between_buttons(from_button, to_button)
-            {
-                // use recursion to find the shortest length that the next robot down the line can take:
-                let new_sub_sequence_length =
-                    self.find_min_button_presses(&button_sequence, depth - 1, cache);
-                if new_sub_sequence_length < min_sub_sequence_length {
-                    min_sub_sequence_length = new_sub_sequence_length;
-                }
-            }
-
+            // for each button to the next, use recursion to find the shortest length that the next robot down the line can take
+            let (_, min_sub_sequence_length) = self
+                .find_best_sequence_between_buttons(from_button, to_button, self, depth - 1, cache)
+                .unwrap();
             total_sequence_length += min_sub_sequence_length;
 
             from_button = to_button;
@@ -258,19 +204,69 @@ impl Keypad {
         total_sequence_length
     }
 
+    /// Same recursion as `find_min_button_presses`, but builds the presses of the human instead of counting them.
+    fn expand_button_presses(
+        &self,
+        button_sequence: &str,
+        depth: u8,
+        cache: &mut HashMap<(String, u8), u64>,
+    ) -> String {
+        if depth == 0 {
+            return button_sequence.to_string();
+        }
+
+        let mut result = String::new();
+        let mut from_button = 'A';
+        for to_button in button_sequence.chars() {
+            let (best_sequence, _) = self
+                .find_best_sequence_between_buttons(from_button, to_button, self, depth - 1, cache)
+                .unwrap();
+            result += &self.expand_button_presses(&best_sequence, depth - 1, cache);
+            from_button = to_button;
+        }
+        result
+    }
+
+    /// Finds the sequence on the `directional` keypad above this keypad to move from one button to the next and press it,
+    /// which takes the fewest presses for the human at the top of the chain (`depth` keypads higher).
+    fn find_best_sequence_between_buttons(
+        &self,
+        from_button: char,
+        to_button: char,
+        directional: &Keypad,
+        depth: u8,
+        cache: &mut HashMap<(String, u8), u64>,
+    ) -> Option<(String, u64)> {
+        self.get_button_sequences_between_buttons(from_button, to_button)?
+            .into_iter()
+            .map(|sequence| {
+                let presses = directional.find_min_button_presses(&sequence, depth, cache);
+                (sequence, presses)
+            })
+            .min_by_key(|(_, presses)| *presses)
+    }
+
     fn get_button_sequences_between_buttons(
         &self,
         from_button: char,
         to_button: char,
-    ) -> Vec<String> {
+    ) -> Option<Vec<String>> {
         if from_button == to_button {
-            return Vec::from(["A".to_string()]);
+            return self
+                .buttons
+                .contains_key(&from_button)
+                .then(|| Vec::from(["A".to_string()]));
         }
 
-        self.buttons[&from_button].sequences_to_button[&to_button]
+        let sequences = self
+            .buttons
+            .get(&from_button)?
+            .sequences_to_button
+            .get(&to_button)?
             .iter()
             .map(|s| s.clone() + "A")
-            .collect::<Vec<String>>()
+            .collect::<Vec<String>>();
+        Some(sequences)
     }
 }
 
@@ -308,10 +304,24 @@ impl Keypad {
         )
     }
 
+    /// Every character is a button, except for gaps marked with '#' or a space.
+    /// Lines may have different lengths, missing positions are gaps as well.
     fn from_text(text: &str) -> Keypad {
+        let width = text
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
         let grid = text
             .lines()
-            .map(|line| line.chars().collect::<Vec<_>>())
+            .map(|line| {
+                let mut row = line
+                    .chars()
+                    .map(|c| if c == ' ' { '#' } else { c })
+                    .collect::<Vec<_>>();
+                row.resize(width, '#');
+                row
+            })
             .collect::<Vec<Vec<char>>>();
 
         let mut routes = HashMap::new();
@@ -319,22 +329,29 @@ impl Keypad {
         // use a mod/divide approach to iterate over all buttons
         // use a second loop to get button combinations
         // find the shortest path between to and also store the reverse of it.
-        let width = grid[0].len();
         let height = grid.len();
         for from_i in 0..width * height {
+            let from_x = from_i % width;
+            let from_y = from_i / width;
+            let from_button = grid[from_y][from_x];
+            if from_button == '#' {
+                continue;
+            }
+            // make sure buttons without a route to other buttons are still known
+            routes.entry(from_button).or_insert(Vec::new());
+
             for to_i in from_i + 1..width * height {
-                let from_x = from_i % width;
-                let from_y = from_i / width;
                 let to_x = to_i % width;
                 let to_y = to_i / width;
-
-                let from_button = grid[from_y][from_x];
                 let to_button = grid[to_y][to_x];
-                if from_button == '#' || to_button == '#' {
+                if to_button == '#' {
                     continue;
                 }
 
-                let routes_from_to_to = find_routes(&grid, from_x, from_y, to_x, to_y).unwrap();
+                // gaps can split a keypad in separate parts, buttons in another part can't be reached
+                let Some(routes_from_to_to) = find_routes(&grid, from_x, from_y, to_x, to_y) else {
+                    continue;
+                };
                 let routes_to_to_from = reverse_routes(&routes_from_to_to);
                 routes
                     .entry(from_button)
@@ -365,7 +382,7 @@ fn reverse_routes(routes_from_to_to: &Vec<String>) -> Vec<String> {
     result
 }
 
-fn reverse_route(route: &String) -> String {
+fn reverse_route(route: &str) -> String {
     route
         .chars()
         .rev()
@@ -381,72 +398,10 @@ fn reverse_route(route: &String) -> String {
 
 impl SequenceCache {
     fn new(routes: Vec<(char, Vec<String>)>) -> SequenceCache {
-        // used for part 1: calculate a rank for each route.
-        let mut best_route_to_other_buttons = HashMap::new();
-        for (to_button, options) in routes.iter() {
-            best_route_to_other_buttons.insert(
-                *to_button,
-                options
-                    .iter()
-                    .min_by(|a, b| rank(a).cmp(&rank(b)))
-                    .unwrap()
-                    .clone(),
-            );
-        }
-
         SequenceCache {
             sequences_to_button: routes.into_iter().collect(),
-            best_sequence_to_button: best_route_to_other_buttons,
-        }
-    }
-}
-
-fn rank(sequence: &String) -> usize {
-    // This works if we aren't going to deep, but this idea did not work out as an approach for 25 robots.
-    // moving from and pressing a certain button takes a fixed minimum number of steps.
-    // this fails for a lot of robots, because a long path for a certain robot might result
-    // in a better path for robot further up the chain.
-    // it worked out for the two directional robots at first, so I kept it in
-    let mut result = 0;
-    let mut from_button = sequence.chars().nth(0).unwrap();
-    for to_button in sequence.chars().skip(1) {
-        if from_button == to_button {
-            result += 1;
-        } else {
-            match (from_button, to_button) {
-                ('A', '^') => result += 2,
-                ('A', '>') => result += 2,
-                ('A', 'v') => result += 3,
-                ('A', '<') => result += 4,
-
-                ('^', 'A') => result += 2,
-                ('^', '>') => result += 3,
-                ('^', 'v') => result += 2,
-                ('^', '<') => result += 3,
-
-                ('>', 'A') => result += 2,
-                ('>', '^') => result += 3,
-                ('>', 'v') => result += 2,
-                ('>', '<') => result += 3,
-
-                ('v', 'A') => result += 3,
-                ('v', '^') => result += 2,
-                ('v', '>') => result += 2,
-                ('v', '<') => result += 2,
-
-                ('<', 'A') => result += 4,
-                ('<', '^') => result += 3,
-                ('<', '>') => result += 3,
-                ('<', 'v') => result += 2,
-                _ => panic!(
-                    "This combination should not exist? {}->{}",
-                    from_button, to_button
-                ),
-            }
         }
-        from_button = to_button;
     }
-    result
 }
 
 /******************************************************/
@@ -471,7 +426,7 @@ impl PartialOrd for Work {
 }
 
 fn find_routes(
-    grid: &Vec<Vec<char>>,
+    grid: &[Vec<char>],
     start_x: usize,
     start_y: usize,
     end_x: usize,
@@ -504,7 +459,7 @@ fn find_routes(
         // check if we have been to this location, using that direction.
         // we might have found an alternative route to this state, which is fine.
         // to keep the algorithm simple: just continue resolving it.
-        let key = current.node.clone();
+        let key = current.node;
         let visited_distance = visited.entry(key).or_insert(current.route.len());
         // when the new route is worse, no need to continue down this path:
         if *visited_distance >= current.route.len() {
@@ -520,7 +475,7 @@ fn find_routes(
     }
 }
 
-fn get_neighbours(current: Work, grid: &Vec<Vec<char>>) -> Vec<Work> {
+fn get_neighbours(current: Work, grid: &[Vec<char>]) -> Vec<Work> {
     let neighbours = [
         Work {
             node: (current.node.0 - 1, current.node.1),
@@ -544,7 +499,11 @@ fn get_neighbours(current: Work, grid: &Vec<Vec<char>>) -> Vec<Work> {
         .filter_map(|w| {
             let line = grid.get(w.node.1 as usize)?;
             let &char = line.get(w.node.0 as usize)?;
-            return if char == '#' { None } else { Some(w) };
+            if char == '#' {
+                None
+            } else {
+                Some(w)
+            }
         })
         .collect()
 }
@@ -1,7 +1,9 @@
 ﻿use aoc24_tools::*;
-use std::collections::HashMap;
+use market::{Market, SecretGenerator};
 use std::fs::read_to_string;
 
+mod market;
+
 const DAY: u8 = 22;
 
 fn main() {
@@ -17,86 +19,163 @@ fn main() {
     println!("{}", "\u{1F34C}".repeat(part2 as usize));
 
     print_summary(DAY);
+
+    // optional: cargo run -r -- --top 5 [--window 4] [--secrets 2000] [--steps "<<6,>>5,<<11"] [--modulus 16777216]
+    // the steps and modulus change the generator of this analysis, the parts above always use the monkeys' generator
+    if let Some(k) = get_argument("top") {
+        print_market_analysis(&data, k.parse().unwrap());
+    }
+}
+
+fn print_market_analysis(input: &str, k: usize) {
+    let window = get_argument("window").map_or(4, |w| w.parse().unwrap());
+    let count = get_argument("secrets").map_or(2000, |c| c.parse().unwrap());
+    let generator = match get_argument("steps") {
+        Some(steps) => {
+            let modulus = get_argument("modulus").map_or(PRUNING_NUMBER, |m| m.parse().unwrap());
+            SecretGenerator::from_spec(&steps, modulus).expect("Steps look like <<6,>>5,<<11")
+        }
+        None => SecretGenerator::monkey(),
+    };
+
+    let initial_secret_numbers: Vec<u64> = input.lines().map(|l| l.parse().unwrap()).collect();
+    let market = Market::simulate(&initial_secret_numbers, &generator, count);
+    for (rank, report) in market.top_sequences(window, k).iter().enumerate() {
+        println!(
+            "{}. {:?} earns {} bananas from {} sellers",
+            rank + 1,
+            report.changes,
+            report.bananas,
+            report.sellers.len()
+        );
+        let prices: Vec<_> = report
+            .sellers
+            .iter()
+            .map(|(seller, price)| format!("#{seller}: {price}"))
+            .collect();
+        println!("   {}", prices.join(", "));
+    }
 }
 
 fn solve(input: &str) -> (u64, u16) {
-    let (part1, secret_number_sequences) =
-        measure!({ sum_of_2000th_secret_numbers(input) }, "part1");
-    let part2 = measure!({ find_best_banana_buy(secret_number_sequences) }, "part2");
+    let secret_numbers = measure!({ parse(input) }, "parse");
+    let (part1, bananas_by_sequence) =
+        measure!({ evolve_buyers_in_parallel(&secret_numbers) }, "evolve");
+    let part2 = measure!({ *bananas_by_sequence.iter().max().unwrap() }, "part2");
     (part1, part2)
 }
 
-const PRUNING_NUMBER: u64 = 16777216;
+fn parse(input: &str) -> Vec<u64> {
+    input.lines().map(|l| l.parse::<u64>().unwrap()).collect()
+}
 
-fn calculate_next_secret(secret_number: &mut u64) {
+const PRUNING_NUMBER: u64 = 16777216;
+// the pruning number is 2^24, so the modulo is the same as keeping the lowest 24 bits
+const PRUNING_MASK: u32 = (PRUNING_NUMBER - 1) as u32;
+
+// a price change is in -9..=9, so 4 changes fit in a base-19 number
+const SEQUENCES: usize = 19 * 19 * 19 * 19;
+// the number of buyers that evolve together. The loops over the lanes are plain loops over a fixed size array,
+// which the compiler can turn into SIMD instructions (autovectorisation), nothing here forces that
+const LANES: usize = 8;
+
+// The monkeys' generator only (SecretGenerator::monkey), with the shifts written out and the pruning as a mask,
+// so it runs on u32 lanes. A generator from --steps and --modulus is only used by the market analysis.
+fn calculate_next_secrets(secret_numbers: &mut [u32; LANES]) {
     // each buyer's secret number evolves into the next secret number in the sequence via the following process:
     // - Calculate the result of multiplying the secret number by 64.
     // Then, mix this result into the secret number. Finally, prune the secret number.
-    *secret_number ^= *secret_number << 6;
-    *secret_number %= PRUNING_NUMBER;
     // - Calculate the result of dividing the secret number by 32.
     // Round the result down to the nearest integer. Then, mix this result into the secret number. Finally, prune the secret number.
-    *secret_number ^= *secret_number >> 5;
-    *secret_number %= PRUNING_NUMBER;
     // - Calculate the result of multiplying the secret number by 2048.
     // Then, mix this result into the secret number. Finally, prune the secret number.
-    *secret_number ^= *secret_number << 11;
-    *secret_number %= PRUNING_NUMBER;
+    for secret_number in secret_numbers.iter_mut() {
+        *secret_number ^= (*secret_number << 6) & PRUNING_MASK;
+        *secret_number ^= *secret_number >> 5;
+        *secret_number ^= (*secret_number << 11) & PRUNING_MASK;
+    }
 }
 
-fn sum_of_2000th_secret_numbers(input: &str) -> (u64, Vec<Vec<u8>>) {
-    let mut sequences = Vec::new();
-    let mut sum: u64 = 0;
-
-    let secret_numbers = input.lines().map(|l| l.parse::<u32>().unwrap());
-    for original_secret_number in secret_numbers {
-        let mut working_secret_number = original_secret_number as u64;
-        let mut sequence = Vec::with_capacity(2001);
-        sequence.push((working_secret_number % 10) as u8);
-        for _ in 0..2000 {
-            calculate_next_secret(&mut working_secret_number);
-            sequence.push((working_secret_number % 10) as u8);
+/// Splits the buyers over the available threads, and returns the sum of the 2000th secret numbers,
+/// together with the number of bananas each sequence of 4 price changes would earn.
+fn evolve_buyers_in_parallel(secret_numbers: &[u64]) -> (u64, Vec<u16>) {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    // round up to whole groups of lanes, so only the last chunk has a partial group
+    let chunk_size = secret_numbers
+        .len()
+        .div_ceil(threads)
+        .div_ceil(LANES)
+        .max(1)
+        * LANES;
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = secret_numbers
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || evolve_buyers(chunk)))
+            .collect();
+
+        let mut sum = 0;
+        let mut bananas_by_sequence = vec![0u16; SEQUENCES];
+        for handle in handles {
+            let (chunk_sum, chunk_bananas) = handle.join().unwrap();
+            sum += chunk_sum;
+            for (total, bananas) in bananas_by_sequence.iter_mut().zip(chunk_bananas) {
+                *total += bananas;
+            }
         }
-
-        sum += working_secret_number;
-        sequences.push(sequence);
-    }
-    (sum, sequences)
+        (sum, bananas_by_sequence)
+    })
 }
 
-fn find_best_banana_buy(price_sequences: Vec<Vec<u8>>) -> u16 {
-    // let's collect the number of banana's for each sequence
-    let mut bananas_by_sequence = HashMap::new();
-
-    for price_sequence in price_sequences {
-        // we can only buy the first time a sequence comes by for this seller
-        let mut seller_bananas_by_sequence = HashMap::new();
-
-        let mut previous_price = price_sequence[0];
-        // I have tried to get the key smaller than 4 bytes, but it just doesn't seem possible
-        // the more readable, but a bit slower approach is to use a 4 byte window.
-        let mut key = 0u32;
-        for (i, &price) in price_sequence.iter().skip(1).enumerate() {
-            let difference = 9 + price - previous_price; // use 9 as base to keep numbers positive (u8)
-            key *= 19;
-            key += difference as u32;
-            previous_price = price;
-
-            if i >= 3 {
-                // if not yet in local_cache, remember this price
-                seller_bananas_by_sequence.entry(key).or_insert(price);
-                key %= 19 * 19 * 19; // remove the last part
+fn evolve_buyers(secret_numbers: &[u64]) -> (u64, Vec<u16>) {
+    let mut sum = 0;
+    let mut bananas_by_sequence = vec![0u16; SEQUENCES];
+    // we can only buy the first time a sequence comes by for a seller, the buyers in a group are evolved together,
+    // so remember which group was the last to see a sequence (group + 1, because 0 means never seen)
+    // and use a bit for each buyer in that group
+    let mut seen_by_group = vec![0u32; SEQUENCES];
+    let mut seen_by_lanes = vec![0u8; SEQUENCES];
+
+    for (lane_group, group) in secret_numbers.chunks(LANES).enumerate() {
+        let buyers = group.len();
+        let stamp = lane_group as u32 + 1;
+        // the first price comes from the initial secret number as it is, after the first step it's pruned,
+        // and pruning before that step gives the same result, so the lanes only hold pruned numbers
+        let mut working_secret_numbers = [0u32; LANES];
+        let mut previous_prices = [0u8; LANES];
+        for (lane, &secret_number) in group.iter().enumerate() {
+            working_secret_numbers[lane] = (secret_number % PRUNING_NUMBER) as u32;
+            previous_prices[lane] = (secret_number % 10) as u8;
+        }
+        let mut keys = [0u32; LANES];
+        for i in 0..2000 {
+            calculate_next_secrets(&mut working_secret_numbers);
+
+            for lane in 0..buyers {
+                let price = (working_secret_numbers[lane] % 10) as u8;
+                let difference = 9 + price - previous_prices[lane]; // use 9 as base to keep numbers positive (u8)
+                keys[lane] = (keys[lane] % (19 * 19 * 19)) * 19 + difference as u32;
+                previous_prices[lane] = price;
+
+                if i >= 3 {
+                    let key = keys[lane] as usize;
+                    if seen_by_group[key] != stamp {
+                        seen_by_group[key] = stamp;
+                        seen_by_lanes[key] = 0;
+                    }
+                    let lane_bit = 1 << lane;
+                    if seen_by_lanes[key] & lane_bit == 0 {
+                        seen_by_lanes[key] |= lane_bit;
+                        bananas_by_sequence[key] += price as u16;
+                    }
+                }
             }
         }
 
-        // add the results for this seller to the totals
-        for (key, num_bananas) in seller_bananas_by_sequence {
-            bananas_by_sequence
-                .entry(key)
-                .and_modify(|total_bananas| *total_bananas += num_bananas as u16)
-                .or_insert(num_bananas as u16);
-        }
+        sum += working_secret_numbers[..buyers]
+            .iter()
+            .map(|&s| s as u64)
+            .sum::<u64>();
     }
-
-    *bananas_by_sequence.values().max().unwrap()
+    (sum, bananas_by_sequence)
 }
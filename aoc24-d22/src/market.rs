@@ -0,0 +1,169 @@
+use crate::PRUNING_NUMBER;
+use std::collections::HashMap;
+
+/// One step of the secret number generator: shift the secret, mix (XOR) it into the secret and prune it.
+#[derive(Clone, Copy)]
+pub enum Step {
+    Multiply(u32), // shift left, multiply by 2^n
+    Divide(u32),   // shift right, divide by 2^n and round down
+}
+
+pub struct SecretGenerator {
+    steps: Vec<Step>,
+    modulus: u64,
+}
+
+impl SecretGenerator {
+    /// The generator used by the monkeys: multiply by 64, divide by 32, multiply by 2048, pruning after each step.
+    pub fn monkey() -> SecretGenerator {
+        SecretGenerator {
+            steps: Vec::from([Step::Multiply(6), Step::Divide(5), Step::Multiply(11)]),
+            modulus: PRUNING_NUMBER,
+        }
+    }
+
+    /// Reads steps like `<<6,>>5,<<11` (left shift multiplies, right shift divides).
+    pub fn from_spec(spec: &str, modulus: u64) -> Option<SecretGenerator> {
+        let mut steps = Vec::new();
+        for step in spec.split(',').map(|s| s.trim()) {
+            if let Some(bits) = step.strip_prefix("<<") {
+                steps.push(Step::Multiply(bits.parse().ok()?));
+            } else if let Some(bits) = step.strip_prefix(">>") {
+                steps.push(Step::Divide(bits.parse().ok()?));
+            } else {
+                return None;
+            }
+        }
+        if modulus == 0 {
+            return None;
+        }
+        Some(SecretGenerator { steps, modulus })
+    }
+
+    pub fn next(&self, secret_number: u64) -> u64 {
+        let mut secret_number = secret_number;
+        for step in self.steps.iter() {
+            secret_number ^= match step {
+                Step::Multiply(bits) => secret_number.checked_shl(*bits).unwrap_or(0),
+                Step::Divide(bits) => secret_number.checked_shr(*bits).unwrap_or(0),
+            };
+            secret_number %= self.modulus;
+        }
+        secret_number
+    }
+
+    /// The prices offered by a seller: the last digit of the initial secret and of each of the `count` next secrets.
+    pub fn prices(&self, initial_secret_number: u64, count: usize) -> Vec<u8> {
+        let mut secret_number = initial_secret_number;
+        let mut prices = Vec::with_capacity(count + 1);
+        prices.push((secret_number % 10) as u8);
+        for _ in 0..count {
+            secret_number = self.next(secret_number);
+            prices.push((secret_number % 10) as u8);
+        }
+        prices
+    }
+}
+
+pub struct Market {
+    // the prices offered by each seller, in the order of the input
+    prices: Vec<Vec<u8>>,
+}
+
+pub struct SequenceReport {
+    pub changes: Vec<i8>,
+    pub bananas: u64,
+    // each seller that shows this sequence, with the price at its first occurrence (that's when the monkey sells)
+    pub sellers: Vec<(usize, u8)>,
+}
+
+// a price change is in -9..=9, so a window of changes fits in a base-19 number.
+// 19^15 still fits in a u64, which is plenty.
+const MAX_WINDOW: usize = 15;
+
+impl Market {
+    pub fn simulate(
+        initial_secret_numbers: &[u64],
+        generator: &SecretGenerator,
+        count: usize,
+    ) -> Market {
+        let prices = initial_secret_numbers
+            .iter()
+            .map(|&secret_number| generator.prices(secret_number, count))
+            .collect();
+        Market { prices }
+    }
+
+    /// Returns the `k` sequences of `window` price changes that earn the most bananas, best first.
+    pub fn top_sequences(&self, window: usize, k: usize) -> Vec<SequenceReport> {
+        assert!(
+            window > 0 && window <= MAX_WINDOW,
+            "window must be between 1 and {MAX_WINDOW}"
+        );
+
+        // first pass: count the bananas for each sequence, without remembering who sold them
+        let mut bananas_by_sequence: HashMap<u64, u64> = HashMap::new();
+        for seller in 0..self.prices.len() {
+            for (key, price) in self.first_occurrences(seller, window) {
+                *bananas_by_sequence.entry(key).or_default() += price as u64;
+            }
+        }
+
+        // sort on bananas, and on the key to keep the order stable between runs
+        let mut best: Vec<_> = bananas_by_sequence.into_iter().collect();
+        best.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        best.truncate(k);
+
+        // second pass: only for the best sequences, find the sellers
+        let mut reports: Vec<_> = best
+            .iter()
+            .map(|&(key, bananas)| SequenceReport {
+                changes: decode_key(key, window),
+                bananas,
+                sellers: Vec::new(),
+            })
+            .collect();
+        let index_by_key: HashMap<_, _> = best
+            .iter()
+            .enumerate()
+            .map(|(i, &(key, _))| (key, i))
+            .collect();
+        for seller in 0..self.prices.len() {
+            for (key, price) in self.first_occurrences(seller, window) {
+                if let Some(&i) = index_by_key.get(&key) {
+                    reports[i].sellers.push((seller, price));
+                }
+            }
+        }
+        for report in reports.iter_mut() {
+            report.sellers.sort();
+        }
+        reports
+    }
+
+    fn first_occurrences(&self, seller: usize, window: usize) -> HashMap<u64, u8> {
+        let prices = &self.prices[seller];
+        let modulus = 19u64.pow(window as u32 - 1);
+
+        let mut result = HashMap::new();
+        let mut key = 0u64;
+        for i in 1..prices.len() {
+            let difference = 9 + prices[i] - prices[i - 1];
+            key = (key % modulus) * 19 + difference as u64;
+            if i >= window {
+                result.entry(key).or_insert(prices[i]);
+            }
+        }
+        result
+    }
+}
+
+fn decode_key(key: u64, window: usize) -> Vec<i8> {
+    let mut key = key;
+    let mut changes = vec![0; window];
+    for change in changes.iter_mut().rev() {
+        *change = (key % 19) as i8 - 9;
+        key /= 19;
+    }
+    changes
+}
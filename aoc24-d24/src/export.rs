@@ -0,0 +1,202 @@
+use crate::{parse_to_usize, Bit, Gate, Operation};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+/// Writes the circuit as a graph: one node per input wire (x/y) and one node per gate, named after its output wire.
+/// Gates are grouped by the bit they contribute to and coloured by their operation.
+/// Gates whose output is in `suspicious` get a red outline, and when `values` are given every node shows the value
+/// of its wire.
+pub fn export_circuit(
+    gates: &[Gate],
+    format: GraphFormat,
+    suspicious: &[&str],
+    values: Option<&HashMap<&str, Bit>>,
+) -> String {
+    let bits = get_bit_positions(gates);
+    let suspicious: HashSet<_> = suspicious.iter().copied().collect();
+
+    // group all nodes (input wires & gates) by their bit position, a BTreeMap keeps the bits in order
+    let mut ranks: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for (&wire, &bit) in bits.iter() {
+        ranks.entry(bit).or_default().push(wire);
+    }
+    for wires in ranks.values_mut() {
+        wires.sort();
+    }
+    let gates_by_output: HashMap<_, _> = gates.iter().map(|g| (g.output, g)).collect();
+
+    match format {
+        GraphFormat::Dot => write_dot(&ranks, &gates_by_output, gates, &suspicious, values),
+        GraphFormat::Mermaid => write_mermaid(&ranks, &gates_by_output, gates, &suspicious, values),
+    }
+}
+
+fn write_dot(
+    ranks: &BTreeMap<usize, Vec<&str>>,
+    gates_by_output: &HashMap<&str, &Gate>,
+    gates: &[Gate],
+    suspicious: &HashSet<&str>,
+    values: Option<&HashMap<&str, Bit>>,
+) -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph circuit {{").unwrap();
+    writeln!(dot, "    rankdir=LR;").unwrap();
+    writeln!(dot, "    node [fontname=\"monospace\"];").unwrap();
+    for (bit, wires) in ranks {
+        writeln!(dot, "    subgraph cluster_bit_{bit:02} {{").unwrap();
+        writeln!(dot, "        label=\"bit {bit:02}\";").unwrap();
+        for &wire in wires {
+            match gates_by_output.get(wire) {
+                Some(gate) => {
+                    let (name, color) = operation_style(&gate.operation);
+                    let outline = if suspicious.contains(wire) {
+                        ", color=\"#FF0000\", penwidth=3"
+                    } else {
+                        ""
+                    };
+                    let label = wire_label(values, wire);
+                    writeln!(
+                        dot,
+                        "        \"{wire}\" [label=\"{name}\\n{label}\", shape=box, style=filled, fillcolor=\"{color}\"{outline}];"
+                    )
+                    .unwrap();
+                }
+                None => {
+                    let label = wire_label(values, wire);
+                    writeln!(dot, "        \"{wire}\" [label=\"{label}\", shape=circle];").unwrap()
+                }
+            }
+        }
+        writeln!(dot, "    }}").unwrap();
+    }
+    for gate in gates {
+        for input in gate.inputs {
+            writeln!(dot, "    \"{input}\" -> \"{}\";", gate.output).unwrap();
+        }
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn write_mermaid(
+    ranks: &BTreeMap<usize, Vec<&str>>,
+    gates_by_output: &HashMap<&str, &Gate>,
+    gates: &[Gate],
+    suspicious: &HashSet<&str>,
+    values: Option<&HashMap<&str, Bit>>,
+) -> String {
+    let mut mermaid = String::new();
+    writeln!(mermaid, "flowchart LR").unwrap();
+    for (bit, wires) in ranks {
+        writeln!(mermaid, "    subgraph bit_{bit:02} [bit {bit:02}]").unwrap();
+        for &wire in wires {
+            match gates_by_output.get(wire) {
+                Some(gate) => {
+                    let (name, _) = operation_style(&gate.operation);
+                    writeln!(
+                        mermaid,
+                        "        {}[\"{name} {}\"]:::{}",
+                        node_id(wire),
+                        wire_label(values, wire),
+                        name.to_lowercase()
+                    )
+                    .unwrap();
+                }
+                None => writeln!(
+                    mermaid,
+                    "        {}((\"{}\"))",
+                    node_id(wire),
+                    wire_label(values, wire)
+                )
+                .unwrap(),
+            }
+        }
+        writeln!(mermaid, "    end").unwrap();
+    }
+    for gate in gates {
+        for input in gate.inputs {
+            writeln!(
+                mermaid,
+                "    {} --> {}",
+                node_id(input),
+                node_id(gate.output)
+            )
+            .unwrap();
+        }
+    }
+    for operation in [Operation::AND, Operation::OR, Operation::XOR] {
+        let (name, color) = operation_style(&operation);
+        writeln!(mermaid, "    classDef {} fill:{color}", name.to_lowercase()).unwrap();
+    }
+    if !suspicious.is_empty() {
+        let mut suspicious: Vec<_> = suspicious.iter().map(|wire| node_id(wire)).collect();
+        suspicious.sort();
+        writeln!(
+            mermaid,
+            "    classDef suspicious stroke:#FF0000,stroke-width:4px"
+        )
+        .unwrap();
+        writeln!(mermaid, "    class {} suspicious", suspicious.join(",")).unwrap();
+    }
+    mermaid
+}
+
+fn operation_style(operation: &Operation) -> (&'static str, &'static str) {
+    match operation {
+        Operation::AND => ("AND", "#66CCFF"),
+        Operation::OR => ("OR", "#FFCC66"),
+        Operation::XOR => ("XOR", "#99FF99"),
+    }
+}
+
+fn get_wire_value(values: Option<&HashMap<&str, Bit>>, wire: &str) -> Option<Bit> {
+    values?.get(wire).copied()
+}
+
+// The wire's name, with its value when it's known.
+fn wire_label(values: Option<&HashMap<&str, Bit>>, wire: &str) -> String {
+    match get_wire_value(values, wire) {
+        Some(value) => format!("{wire} = {value}"),
+        None => wire.to_string(),
+    }
+}
+
+// Mermaid has reserved words (like `end`) that can't be node ids, so every wire gets a prefix.
+fn node_id(wire: &str) -> String {
+    format!("w_{wire}")
+}
+
+/// Input wires and z-outputs know their bit from their name, every other gate takes the highest bit of its inputs.
+/// For a ripple-carry adder this puts the carry of bit n in the same group as the sum of bit n.
+fn get_bit_positions<'a>(gates: &[Gate<'a>]) -> HashMap<&'a str, usize> {
+    let mut bits = HashMap::new();
+    for gate in gates {
+        for input in gate.inputs {
+            if input.starts_with('x') || input.starts_with('y') {
+                bits.insert(input, parse_to_usize(&input[1..3]));
+            }
+        }
+    }
+
+    // keep going over the gates until no gate can be placed anymore, the gates aren't sorted in the input
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for gate in gates {
+            if bits.contains_key(gate.output) {
+                continue;
+            }
+            if let (Some(&a), Some(&b)) = (bits.get(gate.inputs[0]), bits.get(gate.inputs[1])) {
+                let bit = if gate.is_z { gate.output_id } else { a.max(b) };
+                bits.insert(gate.output, bit);
+                changed = true;
+            }
+        }
+    }
+    bits
+}
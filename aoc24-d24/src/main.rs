@@ -1,7 +1,10 @@
 ﻿use aoc24_tools::*;
+use export::{export_circuit, GraphFormat};
 use std::collections::{HashMap, VecDeque};
 use std::fs::read_to_string;
 
+mod export;
+
 const DAY: u8 = 24;
 
 fn main() {
@@ -15,6 +18,23 @@ fn main() {
     println!("The wires that need to be swapped to fix the system (Part 2): {part2}");
 
     print_summary(DAY);
+
+    // optional: cargo run -r -- --dot circuit.dot (or --mermaid circuit.mmd), add --values to annotate the wires
+    if let Some(path) = get_argument("dot") {
+        write_circuit_graph(&data, &part2, GraphFormat::Dot, &path);
+    }
+    if let Some(path) = get_argument("mermaid") {
+        write_circuit_graph(&data, &part2, GraphFormat::Mermaid, &path);
+    }
+}
+
+fn write_circuit_graph(input: &str, swapped_wires: &str, format: GraphFormat, path: &str) {
+    let (wires, gates) = parse(input);
+    let suspicious: Vec<_> = swapped_wires.split(',').filter(|w| !w.is_empty()).collect();
+    let values = has_flag("values").then(|| evaluate_all_wires(&wires, &gates));
+    let graph = export_circuit(&gates, format, &suspicious, values.as_ref());
+    std::fs::write(path, graph).unwrap();
+    println!("Circuit written to {path}");
 }
 
 fn solve(input: &str) -> (u64, String) {
@@ -128,6 +148,30 @@ fn process_logic_system(wires: &HashMap<&str, Bit>, gates: &Vec<Gate>) -> Option
     }
 }
 
+/// Like `process_logic_system`, but keeps going until every wire that can get a value has one.
+fn evaluate_all_wires<'a>(
+    wires: &HashMap<&'a str, Bit>,
+    gates: &[Gate<'a>],
+) -> HashMap<&'a str, Bit> {
+    let mut known_wires = wires.clone();
+    let mut remaining_gates = VecDeque::from_iter(gates.iter());
+    let mut breaker = 0;
+    while let Some(gate) = remaining_gates.pop_front() {
+        if let Some(value) = gate.process_strict(&known_wires) {
+            known_wires.insert(gate.output, value);
+            breaker = 0;
+        } else {
+            remaining_gates.push_back(gate);
+            breaker += 1;
+            // a full round without progress: the remaining gates depend on a loop
+            if breaker > remaining_gates.len() {
+                break;
+            }
+        }
+    }
+    known_wires
+}
+
 type Bit = u8;
 const TRUE: Bit = 1;
 const FALSE: Bit = 0;
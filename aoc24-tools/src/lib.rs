@@ -100,3 +100,21 @@ fn print_named_durations() {
     let nanos = sum.as_nanos().to_formatted_string(&Locale::en);
     println!("| {:10} | {sum:^10?} | {nanos:>12} ns |", "total");
 }
+
+/// Returns true when `--{name}` is passed on the command line, e.g. `cargo run -r -- --dot`.
+pub fn has_flag(name: &str) -> bool {
+    let flag = format!("--{name}");
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+/// Returns the value following `--{name}` on the command line, e.g. `cargo run -r -- --out graph.dot`.
+pub fn get_argument(name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}